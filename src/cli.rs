@@ -0,0 +1,91 @@
+//! The `--version` and `--help` output every module should print, so that other modules can
+//! discover it with [`module::available`](crate::module::available).
+
+use serde_json::json;
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write as _;
+
+use crate::exit::ExitCode;
+use crate::negotiate;
+
+/// What `MFEK{module} --help` prints, besides the standard options.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Help<'a> {
+    /// One line saying what the module does.
+    pub about: &'a str,
+    /// Everything after the binary name in the usage line, e.g. `"[OPTIONS] <GLIF>"`.
+    pub usage: &'a str,
+    /// `(flags, description)` pairs, e.g. `("-o, --output <FILE>", "Where to write the result")`.
+    pub options: &'a [(&'a str, &'a str)],
+}
+
+/// `MFEKglif 1.0.0`, or `MFEKglif (“Codename”) 1.0.0` when built with `MFEK_REL_CODENAME`. The
+/// version is always the last space-separated word, which is what [`module::available`] reads.
+///
+/// [`module::available`]: crate::module::available
+pub fn version_string(module: &str, version: &str) -> String {
+    match option_env!("MFEK_REL_CODENAME") {
+        Some(codename) => format!("MFEK{} (“{}”) {}", module, codename, version),
+        None => format!("MFEK{} {}", module, version),
+    }
+}
+
+/// `--version --json`: `{"module":"glif","version":"1.0.0","codename":null,"ipc":"0.0.4-beta1","protocol":1}`,
+/// `protocol` being [`negotiate::PROTOCOL`].
+pub fn version_json(module: &str, version: &str) -> String {
+    json!({
+        "module": module,
+        "version": version,
+        "codename": option_env!("MFEK_REL_CODENAME"),
+        "ipc": negotiate::VERSION,
+        "protocol": negotiate::PROTOCOL,
+    })
+    .to_string()
+}
+
+pub fn help_string(module: &str, version: &str, help: &Help) -> String {
+    let mut options: Vec<(&str, &str)> = help.options.to_vec();
+    options.push(("-h, --help", "Print this help"));
+    options.push(("--version [--json]", "Print version information"));
+    let width = options.iter().map(|(flags, _)| flags.chars().count()).max().unwrap_or(0);
+
+    let mut s = format!("{}\n", version_string(module, version));
+    if !help.about.is_empty() {
+        writeln!(s, "{}", help.about).unwrap();
+    }
+    writeln!(s, "\nUsage: MFEK{} {}\n\nOptions:", module, help.usage).unwrap();
+    for (flags, description) in options {
+        writeln!(s, "  {:width$}  {}", flags, description, width = width).unwrap();
+    }
+    s
+}
+
+/// Handles `--version`, `--version --json` and `-h`/`--help` in `args` (not including the binary
+/// name). Returns the text to print on stdout, or `None` if the module should carry on as usual.
+pub fn std_args_from<S: AsRef<OsStr>>(args: &[S], module: &str, version: &str, help: &Help) -> Option<String> {
+    let has = |flag: &str| args.iter().any(|a| a.as_ref() == flag);
+    if has("--version") {
+        if has("--json") {
+            Some(version_json(module, version) + "\n")
+        } else {
+            Some(version_string(module, version) + "\n")
+        }
+    } else if has("-h") || has("--help") {
+        Some(help_string(module, version, help))
+    } else {
+        None
+    }
+}
+
+/// Call first thing in `main`: if the process was asked for `--version` or `--help`, prints it and
+/// exits.
+pub fn std_args(module: &str, version: &str, help: &Help) {
+    // not env::args(), which panics on the non-UTF-8 paths Unix allows
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    if let Some(out) = std_args_from(&args, module, version, help) {
+        print!("{}", out);
+        ExitCode::Success.exit();
+    }
+}
@@ -0,0 +1,40 @@
+use log;
+use serde_json::Value;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// `$MFEK_CONFIG`, else `mfek/ipc.json` in the user's config directory (`$XDG_CONFIG_HOME`,
+/// `~/.config`, or `%APPDATA%` on Windows).
+pub(crate) fn config_file() -> Option<PathBuf> {
+    if let Some(file) = env::var_os("MFEK_CONFIG") {
+        return Some(PathBuf::from(file));
+    }
+    #[cfg(target_family = "windows")]
+    let dir = env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(target_family = "windows"))]
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+    dir.map(|d| d.join("mfek").join("ipc.json"))
+}
+
+/// One top-level object of the config file, e.g. `"header"`. A missing or unreadable file is the
+/// same as an empty one; a malformed one is logged and otherwise ignored.
+pub(crate) fn section(name: &str) -> Option<Value> {
+    let file = config_file()?;
+    let data = fs::read_to_string(&file).ok()?;
+    match serde_json::from_str::<Value>(&data) {
+        Ok(Value::Object(mut map)) => map.remove(name),
+        Ok(_) => {
+            log::warn!("Config file {:?} is not a JSON object, ignoring it", &file);
+            None
+        }
+        Err(e) => {
+            log::warn!("Config file {:?} is not valid JSON, ignoring it: {}", &file, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! What MFEK modules' exit statuses mean.
+//!
+//! Codes follow `sysexits.h` where it has one, so they also make sense to shell scripts.
+
+use std::fmt;
+use std::process::{self, ExitStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExitCode {
+    Success,
+    /// Bad command line (`EX_USAGE`).
+    UsageError,
+    /// The font (or glyph) given doesn't exist (`EX_NOINPUT`).
+    FontNotFound,
+    /// It exists, but couldn't be read (`EX_DATAERR`).
+    FontCorrupt,
+    /// A module it needs is the wrong version (`EX_CONFIG`).
+    VersionMismatch,
+    /// Stopped on request, e.g. by [`progress`](crate::progress) or `SIGINT` (128 + 2).
+    Cancelled,
+}
+
+impl ExitCode {
+    pub const ALL: [ExitCode; 6] =
+        [ExitCode::Success, ExitCode::UsageError, ExitCode::FontNotFound, ExitCode::FontCorrupt, ExitCode::VersionMismatch, ExitCode::Cancelled];
+
+    pub fn code(self) -> i32 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::UsageError => 64,
+            ExitCode::FontCorrupt => 65,
+            ExitCode::FontNotFound => 66,
+            ExitCode::VersionMismatch => 78,
+            ExitCode::Cancelled => 130,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        ExitCode::ALL.iter().copied().find(|c| c.code() == code)
+    }
+
+    /// Exits the process with this code.
+    pub fn exit(self) -> ! {
+        process::exit(self.code())
+    }
+
+    /// `Ok` if the child succeeded, else what went wrong.
+    pub fn check(status: ExitStatus) -> Result<(), ExitError> {
+        match status.code() {
+            Some(0) => Ok(()),
+            Some(code) => Err(ExitCode::from_code(code).map(ExitError::Module).unwrap_or(ExitError::Unknown(code))),
+            None => Err(ExitError::Killed),
+        }
+    }
+}
+
+impl fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self {
+            ExitCode::Success => "success",
+            ExitCode::UsageError => "usage error",
+            ExitCode::FontNotFound => "font not found",
+            ExitCode::FontCorrupt => "font corrupt",
+            ExitCode::VersionMismatch => "module version mismatch",
+            ExitCode::Cancelled => "cancelled",
+        };
+        write!(f, "{}", what)
+    }
+}
+
+/// So `main` can return it.
+impl From<ExitCode> for process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        process::ExitCode::from(code.code() as u8)
+    }
+}
+
+/// How a child module failed, from its [`ExitStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitError {
+    /// One of ours, never [`ExitCode::Success`].
+    Module(ExitCode),
+    /// A code we don't know.
+    Unknown(i32),
+    /// By a signal, so with no code at all.
+    Killed,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExitError::Module(code) => write!(f, "{} (exit code {})", code, code.code()),
+            ExitError::Unknown(code) => write!(f, "failed (exit code {})", code),
+            ExitError::Killed => write!(f, "killed by a signal"),
+        }
+    }
+}
+
+impl std::error::Error for ExitError {}
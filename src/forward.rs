@@ -0,0 +1,187 @@
+//! Forwarding "open this glyph" to the MFEK process that already has it open.
+//!
+//! The owner of a glyph is whoever holds its [`Lock`]. Owners [`launch`] a listener on a local
+//! socket in the MFEK runtime directory; a second process that's asked to open the same glyph calls
+//! [`forward_open`] and, if the owner accepted the request, exits instead of opening a second editor.
+//!
+//! ```no_run
+//! # use mfek_ipc::{forward, IPCInfo};
+//! let info = IPCInfo::from_glif_path("glif".to_string(), &"A_.glif");
+//! if let Ok(pid) = forward::forward_open(&info) {
+//!     eprintln!("A_.glif is open in pid {}, raised it there", pid);
+//!     std::process::exit(0);
+//! }
+//! // …else open it as usual
+//! ```
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use crate::lock::Lock;
+use crate::{runtime, IPCInfo};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenRequest {
+    /// Process which was asked to open the glyph, and is about to exit.
+    pub from_pid: u32,
+    pub info: IPCInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct OpenReply {
+    accepted: bool,
+}
+
+#[derive(Debug)]
+pub enum ForwardError {
+    /// Nobody has it open (or its owner doesn't listen); open it normally.
+    NoOwner,
+    /// The owner is us.
+    OwnedBySelf,
+    /// The owner didn't accept the request, so open it normally.
+    Refused,
+    Io(io::Error),
+}
+
+impl From<io::Error> for ForwardError {
+    fn from(e: io::Error) -> Self {
+        ForwardError::Io(e)
+    }
+}
+
+fn socket_path(pid: u32) -> io::Result<PathBuf> {
+    Ok(runtime::subdir("sockets")?.join(format!("{}.open.sock", pid)))
+}
+
+#[cfg(target_family = "unix")]
+mod imp {
+    use super::*;
+
+    use std::fs;
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::thread;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub(super) fn request(pid: u32, req: &OpenRequest) -> Result<OpenReply, ForwardError> {
+        let mut stream = UnixStream::connect(socket_path(pid)?).map_err(|e| {
+            log::debug!("Owner pid {} isn't listening for open requests: {:?}", pid, e);
+            ForwardError::NoOwner
+        })?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.write_all((serde_json::to_string(req).unwrap() + "\n").as_bytes())?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        serde_json::from_str(&line).map_err(|e| ForwardError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    fn serve(stream: UnixStream, tx: &Sender<OpenRequest>) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let accepted = match serde_json::from_str::<OpenRequest>(&line) {
+            Ok(req) => {
+                log::info!("Got forwarded open request: {:?}", &req);
+                tx.send(req).is_ok()
+            }
+            Err(e) => {
+                log::error!("Malformed open request {:?}: {}", &line, e);
+                false
+            }
+        };
+        (&stream).write_all((serde_json::to_string(&OpenReply { accepted }).unwrap() + "\n").as_bytes())
+    }
+
+    pub(super) fn listen(path: &Path, tx: Sender<OpenRequest>, stopped: Arc<AtomicBool>) -> io::Result<()> {
+        // left over from a dead process that had our pid
+        let _ = fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+        log::trace!("Listening for open requests on {:?}", path);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        if let Err(e) = serve(stream, &tx) {
+                            log::error!("Error serving open request: {:?}", e);
+                        }
+                    }
+                    Err(e) => log::error!("Error accepting open request: {:?}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Wakes the listener so it can see it was stopped, and removes its socket.
+    pub(super) fn close(path: &Path) {
+        let _ = UnixStream::connect(path);
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+mod imp {
+    use super::*;
+
+    pub(super) fn request(_pid: u32, _req: &OpenRequest) -> Result<OpenReply, ForwardError> {
+        Err(ForwardError::NoOwner)
+    }
+
+    pub(super) fn listen(_path: &Path, _tx: Sender<OpenRequest>, _stopped: Arc<AtomicBool>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "open request forwarding needs Unix domain sockets"))
+    }
+
+    pub(super) fn close(_path: &Path) {}
+}
+
+/// Asks the process holding the lock on `info`'s glyph (or font) to open it. `Ok` is the pid
+/// that accepted; on any `Err`, open it yourself.
+pub fn forward_open(info: &IPCInfo) -> Result<u32, ForwardError> {
+    let owner = Lock::holder(info).ok_or(ForwardError::NoOwner)?;
+    if owner.pid == process::id() {
+        return Err(ForwardError::OwnedBySelf);
+    }
+    let req = OpenRequest { from_pid: process::id(), info: info.clone() };
+    match imp::request(owner.pid, &req)? {
+        OpenReply { accepted: true } => {
+            log::info!("Forwarded open of {:?} to MFEK{} (pid {})", &owner.target, &owner.module, owner.pid);
+            Ok(owner.pid)
+        }
+        OpenReply { accepted: false } => Err(ForwardError::Refused),
+    }
+}
+
+/// Listening for forwarded open requests, until dropped.
+pub struct Listener {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        imp::close(&self.path);
+    }
+}
+
+/// Listens for forwarded open requests, sending each on `tx`; the module should then raise (or
+/// open) `info.glyph`. Requests are accepted as long as `tx`'s receiver is alive, and the returned
+/// [`Listener`] isn't dropped.
+pub fn launch(tx: Sender<OpenRequest>) -> io::Result<Listener> {
+    let path = socket_path(process::id())?;
+    let stopped = Arc::new(AtomicBool::new(false));
+    imp::listen(&path, tx, Arc::clone(&stopped))?;
+    Ok(Listener { path, stopped })
+}
@@ -0,0 +1,155 @@
+#[cfg(target_family = "windows")]
+use ansi_term;
+use colored::Colorize as _;
+
+use chrono::TimeZone;
+
+use std::io::{self, Write as _};
+
+mod render;
+mod style;
+pub use style::{HeaderColors, HeaderFont, HeaderStyle};
+
+static MFEK: &str = r#"
+      ___           ___         ___           ___     
+     /\  \         /\__\       /\__\         /|  |    
+    |::\  \       /:/ _/_     /:/ _/_       |:|  |    
+    |:|:\  \     /:/ /\__\   /:/ /\__\      |:|  |    
+  __|:|\:\  \   /:/ /:/  /  /:/ /:/ _/_   __|:|  |    
+ /::::|_\:\__\ /:/_/:/  /  /:/_/:/ /\__\ /\ |:|__|____
+ \:\~~\  \/__/ \:\/:/  /   \:\/:/ /:/  / \:\/:::::/__/
+  \:\  \        \::/__/     \::/_/:/  /   \::/~~/~    
+   \:\  \        \:\  \      \:\/:/  /     \:\~~\     
+    \:\__\        \:\__\      \::/  /       \:\__\    
+     \/__/         \/__/       \/__/         \/__/    "#;
+
+fn user_style() -> HeaderStyle {
+    HeaderStyle::default().with_user_overrides()
+}
+
+/// The MFEK logo and `module` in FIGlet letters, in the user's preferred [`HeaderStyle`].
+pub fn header(module: &str) -> Vec<u8> {
+    user_style().render(module)
+}
+
+/// [`header`], written to any `io::Write` rather than returned.
+pub fn header_to(module: &str, w: &mut impl io::Write) -> io::Result<()> {
+    user_style().write_to(module, w)
+}
+
+/// [`header`] without escape codes, e.g. for a GUI text widget.
+pub fn header_plain(module: &str) -> String {
+    user_style().render_plain(module)
+}
+
+/// [`header`] as an HTML `<pre>` block, for GUI About boxes.
+pub fn header_html(module: &str) -> String {
+    user_style().render_html(module)
+}
+
+/// [`header`] as a standalone SVG document, for GUI About boxes.
+pub fn header_svg(module: &str) -> String {
+    user_style().render_svg(module)
+}
+
+/// When built with `reproducible-build`, the `SOURCE_DATE_EPOCH` set by distro packagers at compile
+/// time, if any. It takes the place of any compile date a module passes in.
+#[cfg(feature = "reproducible-build")]
+pub fn source_date_epoch() -> Option<i64> {
+    option_env!("SOURCE_DATE_EPOCH").and_then(|sde| sde.trim().parse().ok())
+}
+
+#[cfg(not(feature = "reproducible-build"))]
+pub fn source_date_epoch() -> Option<i64> {
+    None
+}
+
+fn format_compiled<Tz: TimeZone>(compiled: &chrono::DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let now_fmt = "%Y年%m月%d日(%a)　%H時%M分%S秒(%P)　協定世界時%z";
+    chrono_locale::LocaleDate::formatl(compiled, &now_fmt, "ja-JP").to_string()
+}
+
+/// The compile date `compiled` as [`elaborate_header`] prints it in reproducible builds: in UTC,
+/// so the same epoch always gives the same bytes. `None` if it's out of chrono's range, e.g. a
+/// bogus `SOURCE_DATE_EPOCH`.
+pub fn compiled_date_utc(compiled: i64) -> Option<String> {
+    Some(format_compiled(&chrono::Utc.timestamp_opt(compiled, 0).single()?))
+}
+
+fn header_compiled(compiled: i64) -> Option<String> {
+    // a reproducible build mustn't depend on the builder's (or the user's) time zone
+    #[cfg(feature = "reproducible-build")]
+    let date = compiled_date_utc(compiled)?;
+    // else the offset in effect at `compiled`, which across a DST change isn't today's
+    #[cfg(not(feature = "reproducible-build"))]
+    let date = format_compiled(&chrono::Local.timestamp_opt(compiled, 0).single()?);
+    Some(
+        date.chars()
+            .into_iter()
+            .map(|c| {
+                if c.len_utf8() > 1 {
+                    c.to_string().green()
+                } else {
+                    c.to_string().normal()
+                }
+            })
+            .map(|cs| cs.to_string())
+            .collect(),
+    )
+}
+
+/// The "This is MFEK…" build info line printed under the header by [`elaborate_display`].
+///
+/// With the `reproducible-build` feature, `compiled` is ignored in favour of [`source_date_epoch`],
+/// and the date is printed in UTC, so two builds with the same epoch print the same bytes.
+pub fn elaborate_header(module: &str, version: &str, compiled: Option<i64>) -> Vec<u8> {
+    #[cfg(feature = "reproducible-build")]
+    let compiled = {
+        let _ = compiled;
+        source_date_epoch()
+    };
+    let cdate = match compiled.map(|c| (c, header_compiled(c))) {
+        Some((_, Some(date))) => format!(", compiled @ {}.", &date),
+        Some((c, None)) => {
+            log::warn!("Compile date {} is out of range, leaving it out of the header", c);
+            format!(".")
+        }
+        None => format!("."),
+    };
+    let version = match option_env!("MFEK_REL_CODENAME") {
+        Some(codename) => format!(" {} (“{}”)", version, codename),
+        None => format!(" {}", version),
+    };
+    format!("This is MFEK{}{}{}\n", module, version, cdate).into_bytes()
+}
+
+// for graphical applications
+pub fn elaborate_display(module: &str, version: &str, compiled: Option<i64>) {
+    if let Ok(_) = std::env::var("MFEK_SUPPRESS_HEADER") {
+        return;
+    }
+    display(module);
+    let line = elaborate_header(module, version, compiled);
+    if atty::is(atty::Stream::Stderr) {
+        if let Err(_e) = io::stderr().write(&line) {}
+    }
+}
+
+pub fn display(module: &str) {
+    #[cfg(target_family = "windows")]
+    if let Err(e) = ansi_term::enable_ansi_support() {
+        log::warn!("Failed to enable ANSI term on Windows! error: {:?}", e);
+    }
+
+    if let Ok(_) = std::env::var("MFEK_SUPPRESS_HEADER") {
+        return;
+    }
+    if atty::is(atty::Stream::Stderr) {
+        if let Err(e) = header_to(module, &mut io::stderr()) {
+            log::error!("Failed to write MFEK ASCII art header?? error: {:?}", e);
+        }
+    }
+}
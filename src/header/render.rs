@@ -0,0 +1,145 @@
+//! Markup renderers for GUI modules' About boxes, from the same composition as the terminal header.
+
+use super::style::{HeaderColors, HeaderStyle};
+
+use std::fmt::Write as _;
+use std::io;
+
+// SVG metrics, in px, for a 14px monospace font
+const SVG_FONT_SIZE: usize = 14;
+const SVG_CELL_WIDTH: usize = 9;
+const SVG_LINE_HEIGHT: usize = 17;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Named(&'static str),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn css(&self) -> String {
+        match self {
+            Color::Named(name) => name.to_string(),
+            Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Paint {
+    bold: bool,
+    color: Option<Color>,
+}
+
+const UNPAINTED: Paint = Paint { bold: false, color: None };
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl HeaderStyle {
+    /// Each composed line as runs of text sharing one paint; adjacent runs never share a paint.
+    fn painted_lines(&self, module: &str) -> Vec<Vec<(String, Paint)>> {
+        let lines = self.compose(module);
+        let width = lines.iter().map(|(_, m)| m.chars().count()).max().unwrap_or(0);
+        let bold = self.colors != HeaderColors::Plain;
+        lines
+            .into_iter()
+            .map(|(logo, m)| {
+                let mut runs: Vec<(String, Paint)> = vec![(logo, Paint { bold, color: None })];
+                for (i, c) in m.chars().enumerate() {
+                    let color = match self.colors {
+                        HeaderColors::Classic => Some(Color::Named("blue")),
+                        _ => self.colors.module_rgb(i, width).map(|(r, g, b)| Color::Rgb(r, g, b)),
+                    };
+                    let paint = if c.is_whitespace() { UNPAINTED } else { Paint { bold, color } };
+                    match runs.last_mut() {
+                        Some((text, last)) if *last == paint => text.push(c),
+                        _ => runs.push((c.to_string(), paint)),
+                    }
+                }
+                runs.retain(|(text, _)| !text.is_empty());
+                runs
+            })
+            .collect()
+    }
+
+    /// [`render`](HeaderStyle::render), written to any `io::Write` rather than returned.
+    pub fn write_to(&self, module: &str, w: &mut impl io::Write) -> io::Result<()> {
+        w.write_all(&self.render(module))
+    }
+
+    /// The header without any escape codes.
+    pub fn render_plain(&self, module: &str) -> String {
+        String::from_utf8(self.clone().colors(HeaderColors::Plain).render(module)).unwrap()
+    }
+
+    /// The header as an HTML `<pre>` block with a styled `<span>` per run of color.
+    pub fn render_html(&self, module: &str) -> String {
+        let mut html = String::from("<pre class=\"mfek-header\">");
+        let lines: Vec<String> = self
+            .painted_lines(module)
+            .into_iter()
+            .map(|runs| {
+                runs.iter()
+                    .map(|(text, paint)| {
+                        let mut css = vec![];
+                        if paint.bold {
+                            css.push("font-weight:bold".to_string());
+                        }
+                        if let Some(color) = paint.color {
+                            css.push(format!("color:{}", color.css()));
+                        }
+                        if css.is_empty() {
+                            escape(text)
+                        } else {
+                            format!("<span style=\"{}\">{}</span>", css.join(";"), escape(text))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        html.push_str(&lines.join("\n"));
+        html.push_str("</pre>\n");
+        html
+    }
+
+    /// The header as a standalone SVG document, one `<text>` per line. The logo is drawn in
+    /// `currentColor`, so embedders may restyle it.
+    pub fn render_svg(&self, module: &str) -> String {
+        let lines = self.painted_lines(module);
+        let cols = lines.iter().map(|runs| runs.iter().map(|(text, _)| text.chars().count()).sum::<usize>()).max().unwrap_or(0);
+        let (width, height) = (cols * SVG_CELL_WIDTH, lines.len() * SVG_LINE_HEIGHT + 5);
+
+        let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"{2}\" fill=\"currentColor\">",
+            width, height, SVG_FONT_SIZE
+        )
+        .unwrap();
+        for (i, runs) in lines.iter().enumerate() {
+            if runs.is_empty() {
+                continue;
+            }
+            write!(svg, "<text x=\"0\" y=\"{}\" xml:space=\"preserve\">", (i + 1) * SVG_LINE_HEIGHT).unwrap();
+            for (text, paint) in runs {
+                if *paint == UNPAINTED {
+                    svg.push_str(&escape(text));
+                    continue;
+                }
+                svg.push_str("<tspan");
+                if paint.bold {
+                    svg.push_str(" font-weight=\"bold\"");
+                }
+                if let Some(color) = paint.color {
+                    write!(svg, " fill=\"{}\"", color.css()).unwrap();
+                }
+                write!(svg, ">{}</tspan>", escape(text)).unwrap();
+            }
+            svg.push_str("</text>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,249 @@
+use colored::{ColoredString, Colorize as _};
+use figlet_rs::FIGfont;
+use log;
+
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::config;
+
+pub type Rgb = (u8, u8, u8);
+
+/// FIGlet font the module name is drawn in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderFont {
+    /// `slant.flf`, the MFEK default.
+    Slant,
+    /// `standard.flf`, as bundled with `figlet-rs`.
+    Standard,
+    /// Any `.flf` file on disk.
+    File(PathBuf),
+}
+
+impl Default for HeaderFont {
+    fn default() -> Self {
+        HeaderFont::Slant
+    }
+}
+
+impl HeaderFont {
+    fn load(&self) -> FIGfont {
+        match self {
+            HeaderFont::Slant => FIGfont::from_content(include_str!("../../resources/slant.flf")).unwrap(),
+            HeaderFont::Standard => FIGfont::standard().unwrap(),
+            HeaderFont::File(path) => FIGfont::from_file(&path.to_string_lossy()).unwrap_or_else(|e| {
+                log::warn!("Failed to load FIGlet font {:?} ({}), using slant", path, e);
+                HeaderFont::Slant.load()
+            }),
+        }
+    }
+}
+
+/// `slant`, `standard`, or a path to a `.flf` file.
+impl FromStr for HeaderFont {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.trim() {
+            "" => Err(()),
+            "slant" => Ok(HeaderFont::Slant),
+            "standard" => Ok(HeaderFont::Standard),
+            path => Ok(HeaderFont::File(path.into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeaderColors {
+    /// Bold logo, bold blue module name.
+    Classic,
+    /// No escape codes at all.
+    Plain,
+    /// Bold logo, module name in one truecolor.
+    Solid(Rgb),
+    /// Bold logo, module name shaded left to right between two truecolors.
+    Gradient(Rgb, Rgb),
+}
+
+impl Default for HeaderColors {
+    fn default() -> Self {
+        HeaderColors::Classic
+    }
+}
+
+fn parse_rgb(s: &str) -> Result<Rgb, ()> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// `classic`, `plain` (or `none`), `#rrggbb`, or `#rrggbb:#rrggbb` for a gradient.
+impl FromStr for HeaderColors {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.trim() {
+            "classic" => Ok(HeaderColors::Classic),
+            "plain" | "none" => Ok(HeaderColors::Plain),
+            s => match s.split_once(':') {
+                Some((from, to)) => Ok(HeaderColors::Gradient(parse_rgb(from)?, parse_rgb(to)?)),
+                None => Ok(HeaderColors::Solid(parse_rgb(s)?)),
+            },
+        }
+    }
+}
+
+impl HeaderColors {
+    /// Truecolor of module name column `col` of `width`, or `None` where the scheme has none.
+    pub(crate) fn module_rgb(&self, col: usize, width: usize) -> Option<Rgb> {
+        match *self {
+            HeaderColors::Classic | HeaderColors::Plain => None,
+            HeaderColors::Solid(rgb) => Some(rgb),
+            HeaderColors::Gradient(from, to) => {
+                let t = if width > 1 { col as f32 / (width - 1) as f32 } else { 0. };
+                let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                Some((lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2)))
+            }
+        }
+    }
+
+    fn paint_logo(&self, s: &str) -> ColoredString {
+        match self {
+            HeaderColors::Plain => s.normal(),
+            _ => s.bold(),
+        }
+    }
+
+    fn paint_module(&self, s: &str, width: usize) -> String {
+        match self {
+            HeaderColors::Classic => s.blue().bold().to_string(),
+            HeaderColors::Plain => s.to_string(),
+            HeaderColors::Solid((r, g, b)) => s.truecolor(*r, *g, *b).bold().to_string(),
+            HeaderColors::Gradient(..) => s
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    let (r, g, b) = self.module_rgb(i, width).unwrap();
+                    c.to_string().truecolor(r, g, b).bold().to_string()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How [`header`](super::header) draws the MFEK logo and module name.
+///
+/// ```no_run
+/// use mfek_ipc::{HeaderColors, HeaderFont, HeaderStyle};
+/// let style = HeaderStyle::new().font(HeaderFont::Standard).colors(HeaderColors::Gradient((255, 0, 0), (0, 0, 255)));
+/// eprint!("{}", String::from_utf8(style.render("glif")).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderStyle {
+    pub font: HeaderFont,
+    pub colors: HeaderColors,
+    /// Print the module name as plain text if the font lacks any of its glyphs, instead of
+    /// silently dropping those glyphs.
+    pub fallback: bool,
+}
+
+impl Default for HeaderStyle {
+    fn default() -> Self {
+        HeaderStyle { font: HeaderFont::default(), colors: HeaderColors::default(), fallback: true }
+    }
+}
+
+impl HeaderStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn font(mut self, font: HeaderFont) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn colors(mut self, colors: HeaderColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Applies the user's choices: first the `"header"` object of the config file
+    /// (`{"font": "standard", "colors": "#ff0000:#0000ff"}`), then environment variables
+    /// `MFEK_HEADER_FONT` and `MFEK_HEADER_COLORS`. Unparseable values are logged and skipped.
+    pub fn with_user_overrides(mut self) -> Self {
+        let mut font = None;
+        let mut colors = None;
+        if let Some(header) = config::section("header") {
+            font = header.get("font").and_then(|f| f.as_str()).map(|f| f.to_string());
+            colors = header.get("colors").and_then(|c| c.as_str()).map(|c| c.to_string());
+        }
+        font = env::var("MFEK_HEADER_FONT").ok().or(font);
+        colors = env::var("MFEK_HEADER_COLORS").ok().or(colors);
+
+        if let Some(font) = font {
+            match font.parse() {
+                Ok(font) => self.font = font,
+                Err(()) => log::warn!("Ignoring bad header font {:?}", font),
+            }
+        }
+        if let Some(colors) = colors {
+            match colors.parse() {
+                Ok(colors) => self.colors = colors,
+                Err(()) => log::warn!("Ignoring bad header colors {:?}", colors),
+            }
+        }
+        self
+    }
+
+    /// The module name as drawn next to the logo, one string per line.
+    pub(crate) fn module_lines(&self, module: &str) -> Vec<String> {
+        let font = self.font.load();
+        let missing: Vec<char> = module.chars().filter(|c| !font.fonts.contains_key(&(*c as u32))).collect();
+        if self.fallback && !missing.is_empty() {
+            log::debug!("FIGlet font {:?} lacks {:?}, header falls back to plain text", &self.font, &missing);
+            return vec![module.to_string()];
+        }
+        match font.convert(module) {
+            Some(figure) => figure.to_string().lines().map(|l| l.to_string()).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Logo and module name side by side, as `(logo, module)` pairs per line, bottom-aligned.
+    pub(crate) fn compose(&self, module: &str) -> Vec<(String, String)> {
+        let logo: Vec<_> = super::MFEK.lines().collect();
+        let mut module_lines = self.module_lines(module);
+        if module_lines.len() < logo.len() {
+            let mut padded = vec![String::new(); logo.len() - module_lines.len()];
+            padded.append(&mut module_lines);
+            module_lines = padded;
+        }
+        // taller module names are cut from the top, as the logo can't grow
+        let skip = module_lines.len() - logo.len();
+        logo.into_iter().map(|l| l.to_string()).zip(module_lines.into_iter().skip(skip)).collect()
+    }
+
+    pub fn render(&self, module: &str) -> Vec<u8> {
+        let lines = self.compose(module);
+        let width = lines.iter().map(|(_, m)| m.chars().count()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|(logo, m)| self.colors.paint_logo(logo).to_string() + &self.colors.paint_module(m, width))
+            .chain([String::new()])
+            .chain([String::new()])
+            .collect::<Vec<String>>()
+            .join("\n")
+            .as_bytes()
+            .to_owned()
+    }
+}
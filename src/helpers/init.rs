@@ -0,0 +1,123 @@
+//! Making new fonts and glyphs with MFEKinit.
+//!
+//! Runs `MFEKinit ufo -o <font> --upm <UPM> [--family <name>] [--style <name>] [--layer <name>]…`
+//! and `MFEKinit glif -o <glif> -n <name> [-u <hex>]…`.
+
+use super::{run, HelperError};
+use crate::module::KnownModule;
+use crate::subprocess;
+use crate::IPCInfo;
+
+use log;
+
+use std::path::Path;
+use std::process;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontOptions {
+    pub upm: u16,
+    pub family: Option<String>,
+    pub style: Option<String>,
+    /// Besides the foreground layer, which every font has.
+    pub layers: Vec<String>,
+}
+
+impl Default for FontOptions {
+    fn default() -> Self {
+        FontOptions { upm: 1000, family: None, style: None, layers: vec![] }
+    }
+}
+
+impl FontOptions {
+    pub fn upm(mut self, upm: u16) -> Self {
+        self.upm = upm;
+        self
+    }
+
+    pub fn family(mut self, family: &str) -> Self {
+        self.family = Some(family.to_string());
+        self
+    }
+
+    pub fn style(mut self, style: &str) -> Self {
+        self.style = Some(style.to_string());
+        self
+    }
+
+    pub fn layer(mut self, layer: &str) -> Self {
+        self.layers.push(layer.to_string());
+        self
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut args = vec!["--upm".to_string(), self.upm.to_string()];
+        if let Some(family) = &self.family {
+            args.extend(["--family".to_string(), family.clone()]);
+        }
+        if let Some(style) = &self.style {
+            args.extend(["--style".to_string(), style.clone()]);
+        }
+        args.extend(self.layers.iter().flat_map(|l| ["--layer".to_string(), l.clone()]));
+        args
+    }
+}
+
+/// The `.glif` file name the UFO spec gives glyph `name`: capitals get a `_` after them, and
+/// characters illegal in file names (or names reserved on Windows) are escaped with `_`.
+pub fn glif_file_name(name: &str) -> String {
+    const ILLEGAL: &str = "\"*+/:<>?[\\]|";
+    const RESERVED: [&str; 12] = ["con", "prn", "aux", "clock$", "nul", "com1", "com2", "com3", "com4", "lpt1", "lpt2", "lpt3"];
+    let mut file = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_control() || ILLEGAL.contains(c) || (i == 0 && c == '.') {
+            file.push('_');
+        } else {
+            file.push(c);
+            if c.is_uppercase() {
+                file.push('_');
+            }
+        }
+    }
+    let file = file
+        .split('.')
+        .map(|part| if RESERVED.contains(&part.to_lowercase().as_str()) { format!("_{}", part) } else { part.to_string() })
+        .collect::<Vec<_>>()
+        .join(".");
+    format!("{}.glif", file)
+}
+
+fn command() -> Result<process::Command, HelperError> {
+    Ok(process::Command::new(KnownModule::Init.binary()?))
+}
+
+/// Makes a new UFO at `path`, returning an [`IPCInfo`] for it with `parent` (the module calling,
+/// e.g. `"glif"`) as its parent module.
+pub fn new_font(parent: &str, path: impl AsRef<Path>, options: &FontOptions) -> Result<IPCInfo, HelperError> {
+    let path = path.as_ref();
+    let mut command = command()?;
+    command.arg("ufo").arg("-o").arg(path).args(options.args());
+    log::debug!("Creating font {:?} with {:?}", path, options);
+    run(&mut command, subprocess::QUERY_TIMEOUT)?;
+    if !path.is_dir() {
+        return Err(HelperError::BadOutput(format!("no font made at {:?}", path)));
+    }
+    Ok(IPCInfo::from_font_dir(parent.to_string(), &path))
+}
+
+/// Adds glyph `name`, encoded as `unicodes`, to the font of `info`, returning an [`IPCInfo`] for
+/// it.
+pub fn new_glyph(info: &IPCInfo, name: &str, unicodes: &[char]) -> Result<IPCInfo, HelperError> {
+    let font = info.font.as_ref().ok_or(HelperError::NoTarget)?;
+    let path = font.join("glyphs").join(glif_file_name(name));
+    let mut command = command()?;
+    command.arg("glif").arg("-o").arg(&path).args(["-n", name]);
+    for u in unicodes {
+        command.arg("-u").arg(format!("{:04X}", *u as u32));
+    }
+    log::debug!("Creating glyph {} in {:?}", name, font);
+    run(&mut command, subprocess::QUERY_TIMEOUT)?;
+    if !path.is_file() {
+        return Err(HelperError::BadOutput(format!("no glyph made at {:?}", path)));
+    }
+    Ok(IPCInfo::from_glif_path(info.parent_module.clone(), &path))
+}
@@ -0,0 +1,212 @@
+use crate::exit::{ExitCode, ExitError};
+use crate::module::{KnownModule, ModuleError};
+use crate::subprocess::{self, RunError};
+use crate::IPCInfo;
+
+use glifparser::{Glif, Guideline, PointData, IntegerOrFloat::Float};
+use log;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::{iter, process, str as stdstr};
+
+mod session;
+pub use session::MetadataSession;
+
+static KMDBIN: &str = "MFEKmetadata";
+
+/// Where MFEKmetadata is: see [`KnownModule::binary`]. It must be at least the version of this
+/// library.
+pub fn binary() -> Result<PathBuf, ModuleError> {
+    KnownModule::Metadata.binary()
+}
+
+/// [`binary`], logging why not.
+fn command() -> Result<process::Command, ()> {
+    match binary() {
+        Ok(path) => Ok(process::Command::new(path)),
+        Err(e) => {
+            log::error!("Can't query font metadata: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Command line arguments after the font for getting `keys`.
+fn arbitrary_args<'a>(keys: &[&'a str]) -> Vec<&'a str> {
+    iter::once("arbitrary").chain(keys.iter().flat_map(|k| ["-k", *k])).collect()
+}
+
+/// Runs MFEKmetadata once on the font of `info`, returning its stdout.
+fn run(info: &IPCInfo, args: &[&str]) -> Result<String, ()> {
+    match &info.font {
+        Some(font) => run_on(font, args),
+        None => Err(()),
+    }
+}
+
+fn run_on(font: &Path, args: &[&str]) -> Result<String, ()> {
+    let mut command_c = command()?;
+    command_c.arg(font).args(args);
+    log::trace!("Args are {:?}", command_c);
+    let command = match subprocess::run(&mut command_c, subprocess::QUERY_TIMEOUT) {
+        Ok(command) => command,
+        Err(RunError::Failed(o)) => {
+            match ExitCode::check(o.status) {
+                Err(ExitError::Module(ExitCode::FontNotFound)) => log::error!("{} says font {:?} doesn't exist", KMDBIN, font),
+                Err(ExitError::Module(ExitCode::FontCorrupt)) => log::error!("{} couldn't read font {:?}, corrupt?", KMDBIN, font),
+                Err(e) => log::error!("{} {}", KMDBIN, e),
+                Ok(()) => {}
+            }
+            return Err(());
+        }
+        Err(e) => {
+            log::error!("{} {}", KMDBIN, e);
+            return Err(());
+        }
+    };
+
+    if let Ok(data) = stdstr::from_utf8(&command.stdout) {
+        Ok(data.to_string())
+    } else {
+        log::error!("Encoding error?");
+        Err(())
+    }
+}
+
+fn parse_arbitrary(keys: &[&str], jsondata: &str) -> Result<HashMap<String, String>, ()> {
+    let rows: Vec<_> = jsondata.lines().collect();
+
+    let nrows = rows.len();
+
+    if nrows != keys.len() {
+        if keys.len() == 0 {
+            log::warn!("Got nothing from MFEKmetadata, font corrupt?");
+        } else {
+            log::warn!(
+                "Mismatch! Got {} keys, expected {}. Aborting.",
+                nrows,
+                keys.len()
+            );
+        }
+        Err(())
+    } else {
+        let mut hm: HashMap<String, String> = HashMap::new();
+        for (i, line) in rows.iter().enumerate() {
+            log::debug!("Got line from MFEKmetadata: {}", &line);
+            hm.insert(keys[i].to_string(), line.to_string());
+        }
+        Ok(hm)
+    }
+}
+
+fn parse_ascender_descender(asc_desc: HashMap<String, String>) -> Result<(f32, f32), ()> {
+    Ok((
+        asc_desc["ascender"].parse().unwrap(),
+        asc_desc["descender"].parse().unwrap(),
+    ))
+}
+
+/// A guideline's `color`: a `"r,g,b,a"` string as in the UFO spec, or an array of four numbers.
+fn parse_color(color: &serde_json::Value) -> Option<[f32; 4]> {
+    let channels: Vec<f32> = match color {
+        serde_json::Value::String(s) => s.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?,
+        serde_json::Value::Array(a) => a.iter().map(|c| c.as_f64().map(|c| c as f32)).collect::<Option<_>>()?,
+        _ => return None,
+    };
+    match channels[..] {
+        [r, g, b, a] if channels.iter().all(|c| (0. ..=1.).contains(c)) => Some([r, g, b, a]),
+        _ => None,
+    }
+}
+
+/// One guideline as the UFO spec has it: `x` alone is a vertical line, `y` alone a horizontal one,
+/// and otherwise all of `x`, `y` and `angle` are needed. `None` for any other combination.
+fn parse_guideline<PD: PointData>(guideline: &serde_json::Map<String, serde_json::Value>) -> Option<Guideline<PD>> {
+    let number = |k: &str| guideline.get(k).and_then(|v| v.as_f64()).map(|v| v as f32);
+    let (x, y, angle) = match (number("x"), number("y"), number("angle")) {
+        (Some(x), None, None) => (x, 0., 90.),
+        (None, Some(y), None) => (0., y, 0.),
+        (Some(x), Some(y), Some(angle)) if (0. ..=360.).contains(&angle) => (x, y, angle),
+        _ => return None,
+    };
+    let mut glifguideline = Guideline::from_x_y_angle(x, y, Float(angle));
+    if let Some(name) = guideline.get("name").and_then(|n| n.as_str()) {
+        glifguideline = glifguideline.name(name);
+    }
+    if let Some(identifier) = guideline.get("identifier").and_then(|i| i.as_str()) {
+        glifguideline = glifguideline.identifier(identifier);
+    }
+    if let Some(color) = guideline.get("color") {
+        match parse_color(color) {
+            Some(color) => glifguideline = glifguideline.color(color),
+            None => log::warn!("Ignoring bad guideline color {}", color),
+        }
+    }
+    Some(glifguideline)
+}
+
+/// Parses the font guidelines MFEKmetadata prints: a JSON array of UFO guideline objects.
+/// Guidelines the UFO spec doesn't allow are skipped, with a warning.
+pub fn guidelines_from_json<PD: PointData>(json: &str) -> Result<Vec<Guideline<PD>>, ()> {
+    let line = json.lines().next().ok_or(())?;
+    log::trace!("{}", &line);
+    let array: Vec<serde_json::Value> = serde_json::from_str(line).map_err(|e| log::error!("Bad guidelines JSON from {}: {}", KMDBIN, e))?;
+    let mut guidelines = vec![];
+    for guideline in array.iter() {
+        match guideline.as_object().and_then(parse_guideline::<PD>) {
+            Some(glifguideline) => {
+                log::trace!("Adding UFO guideline: {:?}", &glifguideline);
+                guidelines.push(glifguideline);
+            }
+            None => log::warn!("Skipping guideline not allowed by the UFO spec: {}", guideline),
+        }
+    }
+    Ok(guidelines)
+}
+
+pub fn arbitrary(info: &IPCInfo, keys: &[&str]) -> Result<HashMap<String, String>, ()> {
+    log::debug!("Getting arbitrary keys: {:?}", keys);
+    let jsondata = run(info, &arbitrary_args(keys))?;
+    parse_arbitrary(keys, &jsondata)
+}
+
+pub fn ascender_descender(info: &IPCInfo) -> Result<(f32, f32), ()> {
+    parse_ascender_descender(arbitrary(info, &["ascender", "descender"])?)
+}
+
+/// The font's guidelines.
+pub fn guidelines<PD: PointData>(info: &IPCInfo) -> Result<Vec<Guideline<PD>>, ()> {
+    log::debug!("Getting arbitrary keys: {:?}", &["guidelines"]);
+    guidelines_from_json(&run(info, &arbitrary_args(&["guidelines"]))?)
+}
+
+/// Every guideline that applies to a glyph: its font's, and its own.
+#[derive(Debug, Clone)]
+pub struct AllGuidelines<PD: PointData> {
+    pub font: Vec<Guideline<PD>>,
+    pub glyph: Vec<Guideline<PD>>,
+}
+
+impl<PD: PointData> AllGuidelines<PD> {
+    pub fn iter(&self) -> impl Iterator<Item = &Guideline<PD>> {
+        self.font.iter().chain(self.glyph.iter())
+    }
+}
+
+/// The guidelines of `info`'s glyph, read from its `.glif`; none if it has no glyph.
+fn glyph_guidelines<PD: PointData>(info: &IPCInfo) -> Result<Vec<Guideline<PD>>, ()> {
+    let glyph = match &info.glyph {
+        Some(glyph) if glyph.extension().map(|e| e == "glif").unwrap_or(false) => glyph,
+        _ => return Ok(vec![]),
+    };
+    let xml = fs::read_to_string(glyph).map_err(|e| log::error!("Can't read {:?}: {}", glyph, e))?;
+    let glif: Glif<PD> = glifparser::read(&xml).map_err(|e| log::error!("Can't parse {:?}: {:?}", glyph, e))?;
+    Ok(glif.guidelines)
+}
+
+/// [`guidelines`] of `info`'s font, and those of its glyph.
+pub fn all_guidelines<PD: PointData>(info: &IPCInfo) -> Result<AllGuidelines<PD>, ()> {
+    Ok(AllGuidelines { font: guidelines(info)?, glyph: glyph_guidelines(info)? })
+}
@@ -0,0 +1,240 @@
+//! One long-lived MFEKmetadata per font, instead of a process per query.
+//!
+//! The child is started as `MFEKmetadata <font> --ipc-server` (see [`rpc`](crate::rpc)) and must
+//! answer a `ping` with our protocol. Each query is then a JSON-RPC `query` request whose params are
+//! the arguments that would otherwise follow the font on the command line, and whose result is what
+//! MFEKmetadata would have printed. An MFEKmetadata that doesn't answer the `ping` is run once per
+//! query instead, as the free functions of [`helpers::metadata`](super) do.
+
+use super::{arbitrary_args, command, glyph_guidelines, guidelines_from_json, parse_arbitrary, parse_ascender_descender, run_on, AllGuidelines, KMDBIN};
+use crate::negotiate;
+use crate::rpc::{self, Id, Request, Response};
+use crate::subprocess;
+use crate::IPCInfo;
+
+use glifparser::{Guideline, PointData};
+use log;
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long MFEKmetadata has to exit once asked to, before it's killed.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    /// Its stdout, line by line, read on a thread of its own so reads can time out.
+    lines: Receiver<String>,
+}
+
+#[derive(Debug)]
+enum CallError {
+    /// It exited, closed its stdout, or was killed for taking too long.
+    Gone,
+    Failed,
+}
+
+/// Drop-in for the free functions of [`helpers::metadata`](super), for callers making many queries
+/// on one font.
+pub struct MetadataSession {
+    font: PathBuf,
+    server: Option<Server>,
+    /// Whether MFEKmetadata didn't answer as a server, and so is run once per query.
+    fallback: bool,
+    timeout: Duration,
+    next_id: i64,
+}
+
+impl MetadataSession {
+    /// Starts MFEKmetadata on the font of `info`.
+    pub fn new(info: &IPCInfo) -> Result<Self, ()> {
+        let font = info.font.clone().ok_or(())?;
+        command()?;
+        let mut session = MetadataSession { font, server: None, fallback: false, timeout: subprocess::QUERY_TIMEOUT, next_id: 0 };
+        session.start_or_fall_back();
+        Ok(session)
+    }
+
+    /// How long a query may take before MFEKmetadata is killed (and restarted), 30 seconds if not
+    /// set.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether queries go to a long-lived MFEKmetadata, rather than one run per query because it
+    /// doesn't speak `--ipc-server`.
+    pub fn persistent(&self) -> bool {
+        !self.fallback
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut old) = self.server.take() {
+            let _ = old.child.kill();
+            let _ = old.child.wait();
+        }
+    }
+
+    fn start(&mut self) -> Result<(), ()> {
+        self.kill();
+        let mut cmd = command()?;
+        negotiate::stamp(&mut cmd).arg(&self.font).arg(rpc::SERVER_FLAG).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        log::debug!("Starting MFEKmetadata session: {:?}", &cmd);
+        let mut child = cmd.spawn().map_err(|e| log::error!("Failed to start {}: {:?}", KMDBIN, e))?;
+        let (stdin, stdout) = (child.stdin.take().unwrap(), BufReader::new(child.stdout.take().unwrap()));
+        let stderr = BufReader::new(child.stderr.take().unwrap());
+        thread::spawn(move || {
+            for line in stderr.lines().map_while(Result::ok) {
+                log::debug!("{}: {}", KMDBIN, line);
+            }
+        });
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                match line {
+                    Ok(line) if tx.send(line).is_ok() => {}
+                    _ => break,
+                }
+            }
+        });
+        self.server = Some(Server { child, stdin, lines });
+
+        match self.call("ping", Value::Null, subprocess::QUICK_TIMEOUT) {
+            Ok(protocol) if protocol == json!(negotiate::PROTOCOL) => Ok(()),
+            res => {
+                log::debug!("{} didn't answer ping as a server of protocol {}: {:?}", KMDBIN, negotiate::PROTOCOL, res);
+                self.kill();
+                Err(())
+            }
+        }
+    }
+
+    fn start_or_fall_back(&mut self) {
+        if self.start().is_err() {
+            log::warn!("{} doesn't speak {}, running it once per query on {:?} instead", KMDBIN, rpc::SERVER_FLAG, &self.font);
+            self.fallback = true;
+        }
+    }
+
+    /// Sends a request, waiting `timeout` for its result, after which the child is killed.
+    fn call(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value, CallError> {
+        self.next_id += 1;
+        let id = Id::Number(self.next_id);
+        let server = self.server.as_mut().ok_or(CallError::Gone)?;
+        let request = Request { jsonrpc: "2.0".to_string(), id: Some(id.clone()), method: method.to_string(), params };
+        if let Err(e) = writeln!(server.stdin, "{}", serde_json::to_string(&request).unwrap()).and_then(|()| server.stdin.flush()) {
+            log::debug!("Write to {} failed: {:?}", KMDBIN, e);
+            return Err(CallError::Gone);
+        }
+
+        let deadline = Instant::now() + timeout;
+        let response = loop {
+            let line = match server.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    log::error!("{} didn't answer {} in {:?}, killing it", KMDBIN, method, timeout);
+                    self.kill();
+                    return Err(CallError::Gone);
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    log::debug!("{} closed its stdout", KMDBIN);
+                    return Err(CallError::Gone);
+                }
+            };
+            match serde_json::from_str::<Response>(&line) {
+                Ok(response) if response.id.as_ref() == Some(&id) => break response,
+                Ok(response) => log::debug!("Ignoring response to another request from {}: {:?}", KMDBIN, response),
+                Err(e) => {
+                    log::error!("Bad response from {}: {}", KMDBIN, e);
+                    return Err(CallError::Failed);
+                }
+            }
+        };
+        match response {
+            Response { error: Some(e), .. } => {
+                log::error!("{} failed to {} {}: {}", KMDBIN, method, &request.params, e);
+                Err(CallError::Failed)
+            }
+            Response { result, .. } => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+
+    fn request(&mut self, args: &[&str]) -> Result<String, CallError> {
+        match self.call("query", json!(args), self.timeout)? {
+            Value::String(out) => Ok(out),
+            other => {
+                log::error!("Unexpected result from {}: {:?}", KMDBIN, other);
+                Err(CallError::Failed)
+            }
+        }
+    }
+
+    fn alive(&mut self) -> bool {
+        matches!(self.server.as_mut().map(|s| s.child.try_wait()), Some(Ok(None)))
+    }
+
+    /// Runs one query, restarting the child if it has died (or hung).
+    fn query(&mut self, args: &[&str]) -> Result<String, ()> {
+        if !self.fallback && !self.alive() {
+            log::warn!("{} session on {:?} is gone, restarting it", KMDBIN, &self.font);
+            self.start_or_fall_back();
+        }
+        if self.fallback {
+            return run_on(&self.font, args);
+        }
+        match self.request(args) {
+            Err(CallError::Gone) => {
+                log::warn!("{} died during query, restarting it and retrying", KMDBIN);
+                self.start_or_fall_back();
+                if self.fallback {
+                    return run_on(&self.font, args);
+                }
+                self.request(args).map_err(|_| ())
+            }
+            res => res.map_err(|_| ()),
+        }
+    }
+
+    pub fn arbitrary(&mut self, keys: &[&str]) -> Result<HashMap<String, String>, ()> {
+        log::debug!("Getting arbitrary keys: {:?}", keys);
+        let jsondata = self.query(&arbitrary_args(keys))?;
+        parse_arbitrary(keys, &jsondata)
+    }
+
+    pub fn ascender_descender(&mut self) -> Result<(f32, f32), ()> {
+        parse_ascender_descender(self.arbitrary(&["ascender", "descender"])?)
+    }
+
+    pub fn guidelines<PD: PointData>(&mut self) -> Result<Vec<Guideline<PD>>, ()> {
+        log::debug!("Getting arbitrary keys: {:?}", &["guidelines"]);
+        guidelines_from_json(&self.query(&arbitrary_args(&["guidelines"]))?)
+    }
+
+    /// The font's guidelines, and those of `info`'s glyph.
+    pub fn all_guidelines<PD: PointData>(&mut self, info: &IPCInfo) -> Result<AllGuidelines<PD>, ()> {
+        Ok(AllGuidelines { font: self.guidelines()?, glyph: glyph_guidelines(info)? })
+    }
+}
+
+impl Drop for MetadataSession {
+    fn drop(&mut self) {
+        if let Some(mut server) = self.server.take() {
+            let shutdown = Request { jsonrpc: "2.0".to_string(), id: Some(Id::Number(0)), method: "shutdown".to_string(), params: json!(null) };
+            let _ = writeln!(server.stdin, "{}", serde_json::to_string(&shutdown).unwrap());
+            // it also exits at EOF, should it not understand shutdown
+            drop(server.stdin);
+            if !matches!(subprocess::wait_timeout(&mut server.child, SHUTDOWN_TIMEOUT), Ok(Some(_))) {
+                log::warn!("{} didn't exit when asked to, killing it", KMDBIN);
+                let _ = server.child.kill();
+                let _ = server.child.wait();
+            }
+        }
+    }
+}
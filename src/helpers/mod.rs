@@ -1 +1,141 @@
+pub mod init;
 pub mod metadata;
+pub mod pathops;
+pub mod stroke;
+
+use crate::exit::{ExitCode, ExitError};
+use crate::module::ModuleError;
+use crate::subprocess::{self, RunError};
+use crate::{runtime, IPCInfo};
+
+use glifparser::{Glif, PointData};
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Why a helper couldn't do what was asked of its module.
+#[derive(Debug)]
+pub enum HelperError {
+    /// Not installed, or the wrong version.
+    Module(ModuleError),
+    /// The `IPCInfo` has no font (or glyph) to work on.
+    NoTarget,
+    /// It ran, but failed.
+    Exit(ExitError),
+    TimedOut(Duration),
+    /// It succeeded, but not with what was expected of it.
+    BadOutput(String),
+    /// glifparser couldn't write the glyph given, or read the one made.
+    Glif(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for HelperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HelperError::Module(e) => write!(f, "{}", e),
+            HelperError::NoTarget => write!(f, "no font or glyph to work on"),
+            HelperError::Exit(e) => write!(f, "module {}", e),
+            HelperError::TimedOut(t) => write!(f, "module timed out ({:?})", t),
+            HelperError::BadOutput(why) => write!(f, "unexpected output from module: {}", why),
+            HelperError::Glif(e) => write!(f, "glif error: {}", e),
+            HelperError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HelperError {}
+
+impl From<ModuleError> for HelperError {
+    fn from(e: ModuleError) -> Self {
+        HelperError::Module(e)
+    }
+}
+
+impl From<io::Error> for HelperError {
+    fn from(e: io::Error) -> Self {
+        HelperError::Io(e)
+    }
+}
+
+impl From<RunError> for HelperError {
+    fn from(e: RunError) -> Self {
+        match e {
+            RunError::Spawn(e) | RunError::Io(e) => HelperError::Io(e),
+            RunError::Timeout(t) => HelperError::TimedOut(t),
+            RunError::Failed(o) => HelperError::Exit(ExitCode::check(o.status).err().unwrap_or(ExitError::Killed)),
+        }
+    }
+}
+
+/// Runs a module to completion, returning its stdout.
+pub(crate) fn run(command: &mut Command, timeout: Duration) -> Result<Vec<u8>, HelperError> {
+    Ok(subprocess::run(command, timeout)?.stdout)
+}
+
+/// A glyph for a module to work on: the one `IPCInfo` points at, or one in memory.
+#[derive(Debug, Clone, Copy)]
+pub enum GlyphInput<'a, PD: PointData> {
+    Info(&'a IPCInfo),
+    Glif(&'a Glif<PD>),
+}
+
+impl<'a, PD: PointData> From<&'a IPCInfo> for GlyphInput<'a, PD> {
+    fn from(info: &'a IPCInfo) -> Self {
+        GlyphInput::Info(info)
+    }
+}
+
+impl<'a, PD: PointData> From<&'a Glif<PD>> for GlyphInput<'a, PD> {
+    fn from(glif: &'a Glif<PD>) -> Self {
+        GlyphInput::Glif(glif)
+    }
+}
+
+/// A private directory for files going to and coming from a module, removed on drop.
+pub(crate) struct Scratch {
+    dir: PathBuf,
+}
+
+impl Scratch {
+    pub(crate) fn new() -> io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = runtime::subdir("scratch")?.join(format!("{}-{}", process::id(), n));
+        fs::create_dir_all(&dir)?;
+        Ok(Scratch { dir })
+    }
+
+    pub(crate) fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Where the module can read `input` from, writing it out to `name` first if it's in memory.
+    pub(crate) fn input<PD: PointData>(&self, name: &str, input: GlyphInput<PD>) -> Result<PathBuf, HelperError> {
+        match input {
+            GlyphInput::Info(info) => info.glyph.clone().ok_or(HelperError::NoTarget),
+            GlyphInput::Glif(glif) => {
+                let path = self.path(name);
+                fs::write(&path, glifparser::write(glif).map_err(|e| HelperError::Glif(format!("{:?}", e)))?)?;
+                Ok(path)
+            }
+        }
+    }
+
+    /// Reads back the glyph the module wrote to `name`.
+    pub(crate) fn output<PD: PointData>(&self, name: &str) -> Result<Glif<PD>, HelperError> {
+        let xml = fs::read_to_string(self.path(name)).map_err(|e| HelperError::BadOutput(format!("no glyph written: {}", e)))?;
+        glifparser::read(&xml).map_err(|e| HelperError::Glif(format!("{:?}", e)))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
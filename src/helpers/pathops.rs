@@ -0,0 +1,101 @@
+//! Boolean path operations, overlap removal and refiguring with MFEKpathops.
+//!
+//! Runs `MFEKpathops BOOLEAN -i <input.glif> -o <output.glif> -m <operation> [-p <operand.glif>]`
+//! (with no operand, the glyph's contours are combined with each other) and
+//! `MFEKpathops REFIGURE -i <input.glif> -o <output.glif>`.
+
+use super::{run, GlyphInput, HelperError, Scratch};
+use crate::module::KnownModule;
+use crate::subprocess;
+
+use glifparser::{Glif, PointData};
+use log;
+
+use std::process;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BooleanOp {
+    Union,
+    Intersect,
+    Difference,
+    Xor,
+}
+
+impl BooleanOp {
+    fn arg(self) -> &'static str {
+        match self {
+            BooleanOp::Union => "union",
+            BooleanOp::Intersect => "intersect",
+            BooleanOp::Difference => "difference",
+            BooleanOp::Xor => "xor",
+        }
+    }
+}
+
+/// `input` `op` `operand`, or, with no operand, `op` across the contours of `input`.
+pub fn boolean<'a, PD: PointData>(
+    input: impl Into<GlyphInput<'a, PD>>,
+    op: BooleanOp,
+    operand: Option<GlyphInput<'a, PD>>,
+) -> Result<Glif<PD>, HelperError> {
+    boolean_with_timeout(input, op, operand, subprocess::QUERY_TIMEOUT)
+}
+
+/// [`boolean`], giving MFEKpathops `timeout` rather than 30 seconds to finish in.
+pub fn boolean_with_timeout<'a, PD: PointData>(
+    input: impl Into<GlyphInput<'a, PD>>,
+    op: BooleanOp,
+    operand: Option<GlyphInput<'a, PD>>,
+    timeout: Duration,
+) -> Result<Glif<PD>, HelperError> {
+    let scratch = Scratch::new()?;
+    let input = scratch.input("input.glif", input.into())?;
+    let operand = operand.map(|operand| scratch.input("operand.glif", operand)).transpose()?;
+    let mut command = process::Command::new(KnownModule::PathOps.binary()?);
+    command.arg("BOOLEAN").arg("-i").arg(&input).arg("-o").arg(scratch.path("output.glif")).args(["-m", op.arg()]);
+    if let Some(operand) = operand {
+        command.arg("-p").arg(operand);
+    }
+    log::debug!("Running {:?} on {:?}", op, &input);
+    run(&mut command, timeout)?;
+    scratch.output("output.glif")
+}
+
+pub fn union<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, operand: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    boolean(input, BooleanOp::Union, Some(operand.into()))
+}
+
+pub fn intersect<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, operand: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    boolean(input, BooleanOp::Intersect, Some(operand.into()))
+}
+
+/// `input` with `operand` cut out of it.
+pub fn difference<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, operand: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    boolean(input, BooleanOp::Difference, Some(operand.into()))
+}
+
+pub fn xor<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, operand: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    boolean(input, BooleanOp::Xor, Some(operand.into()))
+}
+
+/// Merges the overlapping contours of `input`.
+pub fn remove_overlap<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    boolean(input, BooleanOp::Union, None)
+}
+
+/// Redraws the contours of `input` with as few points as keep its shape.
+pub fn refigure<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>) -> Result<Glif<PD>, HelperError> {
+    refigure_with_timeout(input, subprocess::QUERY_TIMEOUT)
+}
+
+/// [`refigure`], giving MFEKpathops `timeout` rather than 30 seconds to finish in.
+pub fn refigure_with_timeout<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, timeout: Duration) -> Result<Glif<PD>, HelperError> {
+    let scratch = Scratch::new()?;
+    let input = scratch.input("input.glif", input.into())?;
+    let mut command = process::Command::new(KnownModule::PathOps.binary()?);
+    command.arg("REFIGURE").arg("-i").arg(&input).arg("-o").arg(scratch.path("output.glif"));
+    log::debug!("Refiguring {:?}", &input);
+    run(&mut command, timeout)?;
+    scratch.output("output.glif")
+}
@@ -0,0 +1,236 @@
+//! Stroking paths with MFEKstroke.
+//!
+//! Runs `MFEKstroke <CWS|VWS|PAP> -i <input.glif> -o <output.glif> [mode options]`, for
+//! [`ConstantWidth`], [`Variable`] and [`PatternAlongPath`] respectively.
+
+use super::{run, GlyphInput, HelperError, Scratch};
+use crate::module::KnownModule;
+use crate::subprocess;
+
+use glifparser::{Glif, PointData};
+use log;
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinType {
+    Round,
+    Miter,
+    Bevel,
+}
+
+impl JoinType {
+    fn arg(self) -> &'static str {
+        match self {
+            JoinType::Round => "round",
+            JoinType::Miter => "miter",
+            JoinType::Bevel => "bevel",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapType {
+    Round,
+    Square,
+}
+
+impl CapType {
+    fn arg(self) -> &'static str {
+        match self {
+            CapType::Round => "round",
+            CapType::Square => "square",
+        }
+    }
+}
+
+/// The same width all along the path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstantWidth {
+    pub width: f32,
+    pub join: JoinType,
+    pub start_cap: CapType,
+    pub end_cap: CapType,
+    /// Drop the inner contour of closed paths, leaving only the outer.
+    pub remove_internal: bool,
+    /// Drop the outer contour of closed paths, leaving only the inner.
+    pub remove_external: bool,
+}
+
+impl ConstantWidth {
+    pub fn new(width: f32) -> Self {
+        ConstantWidth {
+            width,
+            join: JoinType::Round,
+            start_cap: CapType::Round,
+            end_cap: CapType::Round,
+            remove_internal: false,
+            remove_external: false,
+        }
+    }
+}
+
+/// Widths per point, as stored in the glyph's lib by MFEKglif's variable width stroke tool. These
+/// are only used where the glyph doesn't say.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub join: JoinType,
+    pub start_cap: CapType,
+    pub end_cap: CapType,
+}
+
+impl Default for Variable {
+    fn default() -> Self {
+        Variable { join: JoinType::Round, start_cap: CapType::Round, end_cap: CapType::Round }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatternCopies {
+    Single,
+    Repeated,
+}
+
+/// Copies of the glyph in `pattern` laid along the path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternAlongPath {
+    /// A `.glif` file.
+    pub pattern: PathBuf,
+    pub copies: PatternCopies,
+    /// Of the pattern, in x and y.
+    pub scale: (f32, f32),
+    /// Between repeated copies.
+    pub spacing: f32,
+    /// Stretch the copies to fill the path exactly.
+    pub stretch: bool,
+    /// Offsets of the pattern from the path, along its normal and its tangent.
+    pub normal_offset: f32,
+    pub tangent_offset: f32,
+    /// Center the pattern on the path, rather than putting its baseline there.
+    pub center: bool,
+}
+
+impl PatternAlongPath {
+    pub fn new(pattern: impl Into<PathBuf>) -> Self {
+        PatternAlongPath {
+            pattern: pattern.into(),
+            copies: PatternCopies::Single,
+            scale: (1., 1.),
+            spacing: 0.,
+            stretch: false,
+            normal_offset: 0.,
+            tangent_offset: 0.,
+            center: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrokeMode {
+    ConstantWidth(ConstantWidth),
+    Variable(Variable),
+    PatternAlongPath(PatternAlongPath),
+}
+
+impl From<ConstantWidth> for StrokeMode {
+    fn from(p: ConstantWidth) -> Self {
+        StrokeMode::ConstantWidth(p)
+    }
+}
+
+impl From<Variable> for StrokeMode {
+    fn from(p: Variable) -> Self {
+        StrokeMode::Variable(p)
+    }
+}
+
+impl From<PatternAlongPath> for StrokeMode {
+    fn from(p: PatternAlongPath) -> Self {
+        StrokeMode::PatternAlongPath(p)
+    }
+}
+
+impl StrokeMode {
+    fn subcommand(&self) -> &'static str {
+        match self {
+            StrokeMode::ConstantWidth(_) => "CWS",
+            StrokeMode::Variable(_) => "VWS",
+            StrokeMode::PatternAlongPath(_) => "PAP",
+        }
+    }
+
+    /// Everything after `-i` and `-o`.
+    fn args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![];
+        fn arg(args: &mut Vec<OsString>, k: &str, v: impl ToString) {
+            args.extend([k.into(), v.to_string().into()]);
+        }
+        match self {
+            StrokeMode::ConstantWidth(p) => {
+                arg(&mut args, "--width", p.width);
+                arg(&mut args, "--jointype", p.join.arg());
+                arg(&mut args, "--startcap", p.start_cap.arg());
+                arg(&mut args, "--endcap", p.end_cap.arg());
+                if p.remove_internal {
+                    args.push("--remove-internal".into());
+                }
+                if p.remove_external {
+                    args.push("--remove-external".into());
+                }
+            }
+            StrokeMode::Variable(p) => {
+                arg(&mut args, "--jointype", p.join.arg());
+                arg(&mut args, "--startcap", p.start_cap.arg());
+                arg(&mut args, "--endcap", p.end_cap.arg());
+            }
+            StrokeMode::PatternAlongPath(p) => {
+                arg(&mut args, "--mode", if p.copies == PatternCopies::Repeated { "repeated" } else { "single" });
+                arg(&mut args, "--sx", p.scale.0);
+                arg(&mut args, "--sy", p.scale.1);
+                arg(&mut args, "--spacing", p.spacing);
+                arg(&mut args, "--normal-offset", p.normal_offset);
+                arg(&mut args, "--tangent-offset", p.tangent_offset);
+                if p.stretch {
+                    args.push("--stretch".into());
+                }
+                if p.center {
+                    args.push("--center-pattern".into());
+                }
+                args.extend(["--pattern".into(), p.pattern.clone().into_os_string()]);
+            }
+        }
+        args
+    }
+}
+
+/// Strokes the paths of `input` (the glyph of an `IPCInfo`, or a `Glif`), returning the result.
+/// Neither the input glyph nor its file is changed.
+///
+/// ```no_run
+/// # use mfek_ipc::helpers::stroke::{self, ConstantWidth};
+/// # use mfek_ipc::IPCInfo;
+/// let info = IPCInfo::from_glif_path("glif".to_string(), &"A_.glif");
+/// let stroked: glifparser::Glif<()> = stroke::stroke(&info, ConstantWidth::new(30.)).unwrap();
+/// ```
+pub fn stroke<'a, PD: PointData>(input: impl Into<GlyphInput<'a, PD>>, mode: impl Into<StrokeMode>) -> Result<Glif<PD>, HelperError> {
+    stroke_with_timeout(input, mode, subprocess::QUERY_TIMEOUT)
+}
+
+/// [`stroke`], giving MFEKstroke `timeout` rather than 30 seconds to finish in.
+pub fn stroke_with_timeout<'a, PD: PointData>(
+    input: impl Into<GlyphInput<'a, PD>>,
+    mode: impl Into<StrokeMode>,
+    timeout: Duration,
+) -> Result<Glif<PD>, HelperError> {
+    let mode = mode.into();
+    let scratch = Scratch::new()?;
+    let input = scratch.input("input.glif", input.into())?;
+    let mut command = process::Command::new(KnownModule::Stroke.binary()?);
+    command.arg(mode.subcommand()).arg("-i").arg(&input).arg("-o").arg(scratch.path("output.glif")).args(mode.args());
+    log::debug!("Stroking {:?} with {:?}", &input, &mode);
+    run(&mut command, timeout)?;
+    scratch.output("output.glif")
+}
@@ -1,4 +1,5 @@
 use log;
+use serde::{Deserialize, Serialize};
 
 use std::env;
 use std::path::{Path, PathBuf};
@@ -6,7 +7,7 @@ use std::path::{Path, PathBuf};
 use crate::util::InUfo as _;
 
 #[non_exhaustive]
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct IPCInfo {
     pub parent_module: String,
     pub parent_exe: PathBuf,
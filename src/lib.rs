@@ -1,12 +1,28 @@
 pub static KMDBIN: &str = "ipc.rlib";
 
+pub mod cli;
 pub mod module;
 pub(crate) mod info;
 pub(crate) mod util;
+pub(crate) mod config;
+pub(crate) mod pubsub;
+pub(crate) mod runtime;
+pub(crate) mod subprocess;
 mod header;
+pub mod exit;
+pub mod forward;
 pub mod helpers;
+pub mod lock;
+pub mod negotiate;
 pub mod notifythread;
+pub mod preview;
+pub mod progress;
+pub mod registry;
+pub mod rpc;
+pub mod supervisor;
+pub mod sync;
 
-pub use header::{display as display_header, elaborate_display as display_elaborate_header, header};
+pub use header::{compiled_date_utc, display as display_header, elaborate_display as display_elaborate_header, elaborate_header, header, source_date_epoch};
+pub use header::{header_html, header_plain, header_svg, header_to, HeaderColors, HeaderFont, HeaderStyle};
 pub use util::InUfo; //trait
 pub use info::IPCInfo;
@@ -0,0 +1,179 @@
+//! Advisory locks, so two MFEK processes don't edit the same glyph (or font) at once.
+//!
+//! Locks are files in the MFEK runtime directory (`$XDG_RUNTIME_DIR/mfek/locks`), one per target,
+//! saying which module and process holds it. A lock whose process has died is stale and is taken
+//! over by the next process to ask for it.
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{runtime, IPCInfo};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    /// [`IPCInfo::parent_module`] of the holder.
+    pub module: String,
+    pub pid: u32,
+    /// The glyph or font that's locked.
+    pub target: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another live process holds the lock.
+    Held(LockHolder),
+    /// The `IPCInfo` has neither glyph nor font to lock.
+    NoTarget,
+    Io(io::Error),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockError::Held(h) => write!(f, "{:?} is already open in MFEK{} (pid {})", h.target, h.module, h.pid),
+            LockError::NoTarget => write!(f, "nothing to lock, IPCInfo has no glyph or font"),
+            LockError::Io(e) => write!(f, "I/O error on lock file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        LockError::Io(e)
+    }
+}
+
+/// Held until dropped (or [`release`](Lock::release)d).
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    holder: LockHolder,
+}
+
+/// The glyph if there is one, else the font.
+pub(crate) fn target(info: &IPCInfo) -> Option<&Path> {
+    info.glyph.as_deref().or(info.font.as_deref())
+}
+
+fn lock_file(target: &Path) -> io::Result<PathBuf> {
+    Ok(runtime::subdir("locks")?.join(runtime::key(target) + ".lock"))
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let data = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&data) {
+        Ok(holder) => Some(holder),
+        Err(e) => {
+            log::warn!("Unreadable lock file {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Makes names of temporary files unique between threads.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// How long a stale lock may take to recover, after which whoever was recovering it is taken to
+/// have died doing so.
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Removes the stale lock at `path`, as long as it's still the one whose holder was `inspected`.
+/// Only one process recovers a lock at a time, holding `<lock>.recovering` while it does; `false`
+/// if another is, so try again.
+fn recover(path: &Path, inspected: Option<&LockHolder>) -> io::Result<bool> {
+    let recovering = path.with_extension("recovering");
+    match OpenOptions::new().write(true).create_new(true).open(&recovering) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            let age = fs::metadata(&recovering).and_then(|m| m.modified()).map(|t| t.elapsed().unwrap_or_default());
+            if age.map_or(false, |age| age > RECOVERY_TIMEOUT) {
+                log::warn!("Removing {:?}, left by a process that died recovering the lock", &recovering);
+                let _ = fs::remove_file(&recovering);
+            } else {
+                thread::sleep(Duration::from_millis(10));
+            }
+            return Ok(false);
+        }
+        Err(e) => return Err(e),
+    }
+    // nobody else removes it while we hold `recovering`, nor can anybody lock it while it's there
+    let removed = if read_holder(path).as_ref() == inspected {
+        fs::remove_file(path).or_else(|e| if e.kind() == io::ErrorKind::NotFound { Ok(()) } else { Err(e) })
+    } else {
+        Ok(())
+    };
+    let _ = fs::remove_file(&recovering);
+    removed.map(|()| true)
+}
+
+impl Lock {
+    /// Locks the target of `info` for this process, taking over a stale lock if need be.
+    pub fn acquire(info: &IPCInfo) -> Result<Lock, LockError> {
+        let target = target(info).ok_or(LockError::NoTarget)?;
+        let path = lock_file(target)?;
+        let holder = LockHolder { module: info.parent_module.clone(), pid: process::id(), target: target.to_path_buf() };
+
+        // written whole, then linked into place, so nobody ever reads half a lock
+        let tmp = path.with_extension(format!("{}-{}.tmp", process::id(), NEXT.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&tmp, serde_json::to_string(&holder).unwrap())?;
+        let locked = Lock::link(&tmp, path, holder);
+        let _ = fs::remove_file(&tmp);
+        locked
+    }
+
+    fn link(tmp: &Path, path: PathBuf, holder: LockHolder) -> Result<Lock, LockError> {
+        let deadline = Instant::now() + 2 * RECOVERY_TIMEOUT;
+        loop {
+            match fs::hard_link(tmp, &path) {
+                Ok(()) => {
+                    log::debug!("Locked {:?} ({:?})", &holder.target, &path);
+                    return Ok(Lock { path, holder });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_holder(&path) {
+                    Some(other) if runtime::alive(other.pid) => return Err(LockError::Held(other)),
+                    other => {
+                        log::info!("Recovering stale lock on {:?} (was {:?})", &holder.target, other);
+                        if !recover(&path, other.as_ref())? && Instant::now() > deadline {
+                            return Err(LockError::Io(io::Error::new(io::ErrorKind::TimedOut, "stale lock is taking too long to recover")));
+                        }
+                    }
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Who holds the lock on the target of `info`, if anyone still alive does.
+    pub fn holder(info: &IPCInfo) -> Option<LockHolder> {
+        let path = lock_file(target(info)?).ok()?;
+        read_holder(&path).filter(|h| runtime::alive(h.pid))
+    }
+
+    pub fn info(&self) -> &LockHolder {
+        &self.holder
+    }
+
+    pub fn release(self) {}
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // don't remove a lock somebody took over from us
+        if read_holder(&self.path).as_ref() == Some(&self.holder) {
+            if let Err(e) = fs::remove_file(&self.path) {
+                log::warn!("Failed to remove lock file {:?}: {:?}", &self.path, e);
+            }
+        }
+    }
+}
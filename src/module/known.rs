@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use super::{available_at_least, binaries, version_at_least, ModuleError, Version};
+
+/// What [`KnownModule::available`] found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Found {
+    pub path: PathBuf,
+    /// As it reports it, if it does.
+    pub version: Option<String>,
+    /// `version` is at least [`min_version`](KnownModule::min_version).
+    pub up_to_date: bool,
+}
+
+/// The MFEK modules we know of, so their names needn't be typed as strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KnownModule {
+    Glif,
+    Metadata,
+    Init,
+    Stroke,
+    PathOps,
+    Export,
+    About,
+    /// Third-party or not yet catalogued, by the name given to [`available`](super::available),
+    /// e.g. `"foo"` for `MFEKfoo`.
+    Other(String),
+}
+
+impl KnownModule {
+    pub const ALL: [KnownModule; 7] = [
+        KnownModule::Glif,
+        KnownModule::Metadata,
+        KnownModule::Init,
+        KnownModule::Stroke,
+        KnownModule::PathOps,
+        KnownModule::Export,
+        KnownModule::About,
+    ];
+
+    /// What goes after `MFEK` in its binary's name.
+    pub fn name(&self) -> &str {
+        match self {
+            KnownModule::Glif => "glif",
+            KnownModule::Metadata => "metadata",
+            KnownModule::Init => "init",
+            KnownModule::Stroke => "stroke",
+            KnownModule::PathOps => "pathops",
+            KnownModule::Export => "export",
+            KnownModule::About => "about",
+            KnownModule::Other(name) => name,
+        }
+    }
+
+    /// Every file name its binary may have.
+    pub fn binaries(&self) -> Vec<String> {
+        binaries(self.name())
+    }
+
+    /// The oldest version this library works with. Modules this library runs itself (see
+    /// [`helpers`](crate::helpers)) are versioned in lockstep with it; for the rest, any will do.
+    pub fn min_version(&self) -> &'static str {
+        match self {
+            KnownModule::Metadata | KnownModule::Init | KnownModule::Stroke | KnownModule::PathOps => env!("CARGO_PKG_VERSION"),
+            _ => "0.0.0",
+        }
+    }
+
+    pub fn purpose(&self) -> &'static str {
+        match self {
+            KnownModule::Glif => "Glyph editor",
+            KnownModule::Metadata => "Queries and edits font metadata (fontinfo.plist, guidelines…)",
+            KnownModule::Init => "Creates new fonts and glyphs",
+            KnownModule::Stroke => "Strokes paths: constant, variable and pattern-along-path",
+            KnownModule::PathOps => "Boolean path operations and overlap removal",
+            KnownModule::Export => "Compiles fonts to OpenType",
+            KnownModule::About => "Shows information about MFEK",
+            KnownModule::Other(_) => "Third-party module",
+        }
+    }
+
+    /// Like [`available`](super::available), except any version from
+    /// [`min_version`](Self::min_version) on counts as up to date, and isn't warned about.
+    pub fn available(&self) -> Result<Found, ()> {
+        let min = self.min_version();
+        let (version, path) = available_at_least(self.name(), min)?;
+        let version = match version {
+            Version::UpToDate(v) => Some(v.to_string()),
+            Version::OutOfDate(found) => found,
+        };
+        let up_to_date = version.as_deref().map_or(false, |found| version_at_least(found, min));
+        Ok(Found { path, version, up_to_date })
+    }
+
+    /// [`require`](Self::require), on first use, and remembered after.
+    pub fn binary(&self) -> Result<PathBuf, ModuleError> {
+        static BINARIES: OnceLock<Mutex<HashMap<KnownModule, Result<PathBuf, ModuleError>>>> = OnceLock::new();
+        let mut binaries = BINARIES.get_or_init(Default::default).lock().unwrap();
+        binaries.entry(self.clone()).or_insert_with(|| self.require()).clone()
+    }
+
+    /// Like [`require`](super::require), except any version from
+    /// [`min_version`](Self::min_version) on will do.
+    pub fn require(&self) -> Result<PathBuf, ModuleError> {
+        match self.available() {
+            Ok(Found { path, up_to_date: true, .. }) => Ok(path),
+            Ok(Found { path, version, .. }) => {
+                Err(ModuleError::WrongVersion { module: self.name().to_string(), expected: format!(">= {}", self.min_version()), found: version, path })
+            }
+            Err(()) => Err(ModuleError::Missing(self.name().to_string())),
+        }
+    }
+}
+
+impl fmt::Display for KnownModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MFEK{}", self.name())
+    }
+}
+
+/// `glif`, `MFEKglif` or `mfek-glif`, in any case; unknown names are [`KnownModule::Other`].
+impl FromStr for KnownModule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let lower = s.trim().to_lowercase();
+        let name = lower.strip_prefix("mfek-").or_else(|| lower.strip_prefix("mfek")).unwrap_or(&lower);
+        let name = name.strip_suffix(".exe").unwrap_or(name);
+        if name.is_empty() {
+            return Err(());
+        }
+        Ok(KnownModule::ALL.iter().find(|m| m.name() == name).cloned().unwrap_or_else(|| KnownModule::Other(name.to_string())))
+    }
+}
+
+impl From<&str> for KnownModule {
+    fn from(s: &str) -> Self {
+        s.parse().unwrap_or_else(|()| KnownModule::Other(s.to_string()))
+    }
+}
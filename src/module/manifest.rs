@@ -0,0 +1,122 @@
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::KnownModule;
+
+/// Something a module offers to do, for MFEKglif and friends to put in their menus. `args` follow
+/// the font or glyph on the module's command line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuEntry {
+    pub label: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A module's description of itself, for modules `available` wouldn't otherwise know what to make
+/// of. E.g., as `foo.toml`:
+///
+/// ```toml
+/// name = "foo"
+/// binary = "bin/MFEKfoo"
+/// version = "1.0.0"
+/// file_types = ["glif", "ufo"]
+///
+/// [[menu]]
+/// label = "Foo this glyph"
+/// args = ["--foo"]
+/// ```
+///
+/// or the same as `foo.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// As given to `available`, e.g. `"foo"` for `MFEKfoo`.
+    pub name: String,
+    /// Relative to the directory the manifest is in, if relative.
+    pub binary: PathBuf,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// File extensions, without the `.`.
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    #[serde(default)]
+    pub menu: Vec<MenuEntry>,
+    /// Where it was read from.
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Manifest {
+    /// Reads a `.toml` or `.json` manifest.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut manifest: Manifest = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&data).map_err(|e| e.to_string())?,
+            Some("json") => serde_json::from_str(&data).map_err(|e| e.to_string())?,
+            _ => return Err("not a .toml or .json file".to_string()),
+        };
+        if manifest.name.is_empty() {
+            return Err("no module name".to_string());
+        }
+        if manifest.binary.is_relative() {
+            if let Some(dir) = path.parent() {
+                manifest.binary = dir.join(&manifest.binary);
+            }
+        }
+        manifest.path = path.to_owned();
+        Ok(manifest)
+    }
+
+    pub fn handles(&self, extension: &str) -> bool {
+        self.file_types.iter().any(|t| t.eq_ignore_ascii_case(extension.trim_start_matches('.')))
+    }
+}
+
+/// `mfek/modules` in each XDG data directory, most important first: `$XDG_DATA_HOME` (or
+/// `~/.local/share`), then `$XDG_DATA_DIRS` (or `/usr/local/share:/usr/share`).
+pub fn manifest_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share")));
+    let dirs: Vec<PathBuf> = match env::var_os("XDG_DATA_DIRS").filter(|d| !d.is_empty()) {
+        Some(dirs) => env::split_paths(&dirs).filter(|d| !d.as_os_str().is_empty()).collect(),
+        None => vec![PathBuf::from("/usr/local/share"), PathBuf::from("/usr/share")],
+    };
+    home.into_iter().chain(dirs).map(|d| d.join("mfek").join("modules")).collect()
+}
+
+/// Every manifest in `dirs`. Where two name the same module, the one in the earlier directory
+/// wins; ones that can't be read, or that name one of [`KnownModule::ALL`], are logged and skipped.
+pub fn manifests_in(dirs: &[PathBuf]) -> Vec<Manifest> {
+    let mut manifests: Vec<Manifest> = vec![];
+    for dir in dirs {
+        let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(rd) => rd.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+            Err(_) => continue,
+        };
+        files.sort();
+        for file in files {
+            if !matches!(file.extension().and_then(|e| e.to_str()), Some("toml") | Some("json")) {
+                continue;
+            }
+            match Manifest::read(&file) {
+                Ok(manifest) if KnownModule::ALL.iter().any(|m| m.name() == manifest.name) => {
+                    log::warn!("Ignoring {:?}, MFEK{} is built in and can't be replaced by a manifest", &file, &manifest.name);
+                }
+                Ok(manifest) if manifests.iter().any(|m| m.name == manifest.name) => {
+                    log::debug!("Ignoring {:?}, MFEK{} already has a manifest", &file, &manifest.name);
+                }
+                Ok(manifest) => {
+                    log::debug!("Found manifest {:?} for MFEK{}", &file, &manifest.name);
+                    manifests.push(manifest);
+                }
+                Err(e) => log::warn!("Ignoring bad module manifest {:?}: {}", &file, e),
+            }
+        }
+    }
+    manifests
+}
@@ -0,0 +1,380 @@
+use log;
+
+use std::ffi::OsString;
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str as stdstr;
+
+use crate::subprocess;
+
+mod known;
+mod manifest;
+mod search;
+mod trust;
+pub use known::{Found, KnownModule};
+pub use manifest::{manifest_dirs, manifests_in, Manifest, MenuEntry};
+pub use search::{SearchOrder, SearchPath};
+pub use trust::TrustPolicy;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Version<'caller> {
+    UpToDate(&'caller str),
+    OutOfDate(Option<String>),
+}
+
+impl Version<'_> {
+    pub fn matches(&self) -> bool {
+        match self {
+            Self::UpToDate(_) => true,
+            Self::OutOfDate(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleError {
+    /// No binary of the module anywhere we looked.
+    Missing(String),
+    /// Found, but not the version we need (`found` is `None` if it didn't say).
+    WrongVersion { module: String, expected: String, found: Option<String>, path: PathBuf },
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModuleError::Missing(module) => write!(f, "module MFEK{} is missing; please install it into your PATH", module),
+            ModuleError::WrongVersion { module, expected, found: Some(found), path } => {
+                write!(f, "module MFEK{} at {:?} is version {}, but {} is needed", module, path, found, expected)
+            }
+            ModuleError::WrongVersion { module, expected, found: None, path } => {
+                write!(f, "module MFEK{} at {:?} didn't report its version, but {} is needed", module, path, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
+
+/// Like [`available`], but only a module of exactly `version` will do.
+pub fn require(module: &str, version: &str) -> Result<PathBuf, ModuleError> {
+    match available(module, version) {
+        Ok((Version::UpToDate(_), path)) => Ok(path),
+        Ok((Version::OutOfDate(found), path)) => {
+            Err(ModuleError::WrongVersion { module: module.to_string(), expected: version.to_string(), found, path })
+        }
+        Err(()) => Err(ModuleError::Missing(module.to_string())),
+    }
+}
+
+/// The version a module reported on `--version`: the last space-separated word of
+/// [`cli::version_string`](crate::cli::version_string), or the `"version"` of
+/// [`cli::version_json`](crate::cli::version_json).
+pub fn version_from_output(output: &str) -> Option<String> {
+    let output = output.trim();
+    if output.starts_with('{') {
+        if let Ok(serde_json::Value::Object(o)) = serde_json::from_str(output) {
+            return o.get("version").and_then(|v| v.as_str()).map(|v| v.to_string());
+        }
+    }
+    output.split(' ').last().filter(|v| !v.is_empty()).map(|v| v.to_string())
+}
+
+/// Whether `found` is `min` or newer, comparing `.`-separated numbers (a leading `v` is ignored),
+/// then any `-pre-release` part, which comes before the release itself. `false` if either isn't a
+/// version.
+pub fn version_at_least(found: &str, min: &str) -> bool {
+    fn parse(v: &str) -> Option<(Vec<u64>, Option<&str>)> {
+        let v = v.trim();
+        let v = v.strip_prefix('v').unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (v, None),
+        };
+        let nums = core.split('.').map(|n| n.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        Some((nums, pre))
+    }
+    let ((mut found, found_pre), (mut min, min_pre)) = match (parse(found), parse(min)) {
+        (Some(found), Some(min)) => (found, min),
+        _ => return false,
+    };
+    let len = found.len().max(min.len());
+    found.resize(len, 0);
+    min.resize(len, 0);
+    match found.cmp(&min) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => match (found_pre, min_pre) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(f), Some(m)) => f >= m,
+        },
+    }
+}
+
+pub fn binaries(module: &str) -> Vec<String> {
+    #[cfg(target_family = "windows")]
+    let mut binaries;
+    #[cfg(not(target_family = "windows"))]
+    let binaries;
+    binaries = vec![format!("MFEK{}", module), format!("mfek-{}", module)];
+    #[cfg(target_family = "windows")]
+    binaries.extend([format!("MFEK{}.exe", module), format!("mfek-{}.exe", module)]);
+
+    binaries
+}
+
+/// A binary that would have been used, but failed the [`TrustPolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discovery<'caller> {
+    pub found: Option<(Version<'caller>, PathBuf)>,
+    /// In search order; all of them come before `found`.
+    pub rejected: Vec<Rejected>,
+}
+
+fn executable(pb: &Path) -> bool {
+    let md = match fs::metadata(pb) {
+        Ok(md) if md.is_file() => md,
+        _ => return false,
+    };
+    log::debug!("Got metadata: {:?}", &md);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        md.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        true
+    }
+}
+
+/// Which versions of a module the caller can use.
+#[derive(Debug, Clone, Copy)]
+enum Wanted<'caller> {
+    Exactly(&'caller str),
+    /// As [`KnownModule`]s are, by their [`min_version`](KnownModule::min_version).
+    AtLeast(&'caller str),
+}
+
+impl<'caller> Wanted<'caller> {
+    fn version(&self) -> &'caller str {
+        match self {
+            Wanted::Exactly(v) | Wanted::AtLeast(v) => *v,
+        }
+    }
+
+    fn accepts(&self, found: &str) -> bool {
+        match self {
+            Wanted::Exactly(v) => found == *v,
+            Wanted::AtLeast(v) => version_at_least(found, v),
+        }
+    }
+}
+
+impl fmt::Display for Wanted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Wanted::Exactly(v) => write!(f, "{}", v),
+            Wanted::AtLeast(v) => write!(f, ">= {}", v),
+        }
+    }
+}
+
+/// Which version the module binary `pb` is. Only warns if it's not one `wanted`.
+fn probe<'caller>(pb: &Path, module: &str, wanted: Wanted<'caller>) -> Version<'caller> {
+    let version = wanted.version();
+    let ret;
+    let degraded = if let Ok(o) = subprocess::run(process::Command::new(pb).args(&["--version"]), subprocess::QUICK_TIMEOUT) {
+        if let Ok(Some(data)) = stdstr::from_utf8(&o.stdout).map(version_from_output) {
+            if data == version {
+                ret = Version::UpToDate(version);
+                "OK".to_string()
+            } else if wanted.accepts(&data) {
+                let s = format!("version {}", &data);
+                ret = Version::OutOfDate(Some(data));
+                s
+            } else {
+                let s = format!("unexpected version {}", &data);
+                ret = Version::OutOfDate(Some(data));
+                s
+            }
+        } else {
+            ret = Version::OutOfDate(None);
+            "no readable version information".to_string()
+        }
+    } else {
+        ret = Version::OutOfDate(None);
+        "no version information".to_string()
+    };
+
+    log::info!("{:?} found ({})", pb, &degraded);
+
+    let accepted = match &ret {
+        Version::UpToDate(_) => true,
+        Version::OutOfDate(found) => found.as_deref().map_or(false, |found| wanted.accepts(found)),
+    };
+    if !accepted {
+        log::warn!("Got {} from MFEK{}. Your experience may be degraded. Please either update MFEK{1} or this program so that the version of MFEK{1} it expects matches. (Expected MFEK{1} {}.)", degraded, module, wanted);
+    }
+    ret
+}
+
+/// Finds `module` as configured by the user: see [`SearchPath::from_env`].
+pub fn available<'caller>(module: &str, version: &'caller str) -> Result<(Version<'caller>, PathBuf), ()> {
+    available_in(&SearchPath::from_env(), module, version)
+}
+
+/// [`discover`], without saying what was rejected.
+pub fn available_in<'caller>(search: &SearchPath, module: &str, version: &'caller str) -> Result<(Version<'caller>, PathBuf), ()> {
+    discover(search, module, version).found.ok_or(())
+}
+
+/// The manifest of `module` in `search`. The modules of [`KnownModule::ALL`] are never taken from
+/// manifests, so that a manifest can't stand in for one.
+fn manifest_of<'s>(search: &'s SearchPath, module: &str) -> Option<&'s Manifest> {
+    if KnownModule::ALL.iter().any(|m| m.name() == module) {
+        return None;
+    }
+    search.manifests.iter().find(|m| m.name == module)
+}
+
+/// `probed`, or for a binary that didn't report its version, the version its manifest gives.
+fn with_manifest_version<'caller>(manifest: &Manifest, probed: Version<'caller>, version: &'caller str) -> Version<'caller> {
+    let listed = match &manifest.version {
+        Some(listed) => listed,
+        None => return probed,
+    };
+    match probed {
+        Version::OutOfDate(None) => {
+            log::info!("Taking MFEK{}'s version from its manifest {:?}: {}", &manifest.name, &manifest.path, listed);
+            if listed == version {
+                Version::UpToDate(version)
+            } else {
+                Version::OutOfDate(Some(listed.clone()))
+            }
+        }
+        Version::UpToDate(found) if found != listed => {
+            log::warn!("MFEK{} is version {}, but its manifest {:?} says {}", &manifest.name, found, &manifest.path, listed);
+            probed
+        }
+        Version::OutOfDate(Some(ref found)) if found != listed => {
+            log::warn!("MFEK{} is version {}, but its manifest {:?} says {}", &manifest.name, found, &manifest.path, listed);
+            probed
+        }
+        probed => probed,
+    }
+}
+
+/// Where `module` is in `search`, without running it.
+fn locate(search: &SearchPath, module: &str, rejected: &mut Vec<Rejected>) -> Option<PathBuf> {
+    let candidates: Vec<PathBuf> = match search.pinned.get(module) {
+        Some(pinned) => {
+            log::debug!("MFEK{} is pinned to {:?}", module, pinned);
+            if !executable(pinned) {
+                log::error!("Module MFEK{} is pinned to {:?}, which is not an executable file.", module, pinned);
+                return None;
+            }
+            vec![pinned.clone()]
+        }
+        None => {
+            let modules = binaries(module);
+            let manifest = manifest_of(search, module).map(|m| m.binary.clone());
+            manifest
+                .chain(search.dirs().into_iter().flat_map(|path| {
+                    modules
+                        .iter()
+                        .map(move |mn| [path.as_os_str(), &OsString::from(mn.clone())].iter().collect::<PathBuf>())
+                        .collect::<Vec<_>>()
+                }))
+                .collect()
+        }
+    };
+
+    for pb in candidates {
+        log::debug!("Checking {:?} for MFEK{}", &pb, module);
+        if !executable(&pb) {
+            continue;
+        }
+        if let Err(reason) = search.trust.check(&pb) {
+            log::warn!("Not running {:?} for MFEK{}: {}", &pb, module, &reason);
+            rejected.push(Rejected { path: pb, reason });
+            continue;
+        }
+        return Some(pb);
+    }
+    None
+}
+
+/// [`available`], except any version from `min` on is fine, and not warned about.
+pub(crate) fn available_at_least<'caller>(module: &str, min: &'caller str) -> Result<(Version<'caller>, PathBuf), ()> {
+    discover_wanted(&SearchPath::from_env(), module, Wanted::AtLeast(min)).found.ok_or(())
+}
+
+/// Finds `module` in `search`: its pinned binary if it has one, else the binary of its manifest (if
+/// it's not one of [`KnownModule::ALL`]) or the first of any of its [`binaries`] names in the search
+/// directories, whichever is executable and passes the trust policy first. Then asks it its
+/// version, which for a manifest's binary that doesn't say is the manifest's.
+pub fn discover<'caller>(search: &SearchPath, module: &str, version: &'caller str) -> Discovery<'caller> {
+    discover_wanted(search, module, Wanted::Exactly(version))
+}
+
+fn discover_wanted<'caller>(search: &SearchPath, module: &str, wanted: Wanted<'caller>) -> Discovery<'caller> {
+    let mut discovery = Discovery { found: None, rejected: vec![] };
+    if let Some(pb) = locate(search, module, &mut discovery.rejected) {
+        let mut probed = probe(&pb, module, wanted);
+        if let Some(manifest) = manifest_of(search, module).filter(|m| m.binary == pb) {
+            probed = with_manifest_version(manifest, probed, wanted.version());
+        }
+        discovery.found = Some((probed, pb));
+        return discovery;
+    }
+
+    log::error!(
+        "Module MFEK{} is not available. MFEK is modular software; it will still run but some \
+        features will not be available. For the best experience, please install all available \
+        MFEK modules into your PATH.",
+        module
+    );
+
+    discovery
+}
+
+/// An installed module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listing {
+    pub module: KnownModule,
+    pub binary: PathBuf,
+    pub manifest: Option<Manifest>,
+}
+
+/// Every [`KnownModule`], and every module with a manifest, that's installed. Nothing is run to
+/// find out.
+pub fn list_modules() -> Vec<Listing> {
+    list_modules_in(&SearchPath::from_env())
+}
+
+pub fn list_modules_in(search: &SearchPath) -> Vec<Listing> {
+    let mut names: Vec<String> = KnownModule::ALL.iter().map(|m| m.name().to_string()).collect();
+    for manifest in &search.manifests {
+        if manifest_of(search, &manifest.name).is_some() && !names.contains(&manifest.name) {
+            names.push(manifest.name.clone());
+        }
+    }
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let binary = locate(search, &name, &mut vec![])?;
+            let manifest = manifest_of(search, &name).cloned();
+            Some(Listing { module: KnownModule::from(name.as_str()), binary, manifest })
+        })
+        .collect()
+}
@@ -0,0 +1,159 @@
+use log;
+
+use std::collections::HashMap;
+use std::env::{self, current_exe};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::manifest::{manifest_dirs, manifests_in};
+use super::{Manifest, TrustPolicy};
+use crate::config;
+
+/// Whether `PATH` or the directory of the running executable (the "bundle" it came in) is searched
+/// first. Either way, extra directories come before both.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchOrder {
+    PathFirst,
+    BundleFirst,
+}
+
+impl Default for SearchOrder {
+    fn default() -> Self {
+        SearchOrder::PathFirst
+    }
+}
+
+/// `path-first` or `bundle-first`.
+impl FromStr for SearchOrder {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.trim() {
+            "path-first" => Ok(SearchOrder::PathFirst),
+            "bundle-first" => Ok(SearchOrder::BundleFirst),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where [`available_in`](super::available_in) looks for modules.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchPath {
+    /// Searched before `PATH` and the bundle directory, in order.
+    pub extra: Vec<PathBuf>,
+    pub order: SearchOrder,
+    /// Modules (by the name given to `available`, e.g. `"metadata"`) to use from exactly this
+    /// binary, wherever it is and whatever it's called.
+    pub pinned: HashMap<String, PathBuf>,
+    pub trust: TrustPolicy,
+    /// Their binaries come before the search directories, but after pinned ones. Those naming one of
+    /// [`KnownModule::ALL`](super::KnownModule::ALL) are ignored.
+    pub manifests: Vec<Manifest>,
+}
+
+impl SearchPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The user's configuration: first the `"modules"` object of the config file, e.g.
+    /// `{"path": ["/opt/mfek/bin"], "order": "bundle-first", "pinned": {"metadata": "/src/MFEKmetadata/target/debug/MFEKmetadata"}}`,
+    /// then the environment, where `MFEK_MODULE_PATH` (separated like `PATH`) comes before the
+    /// config file's `"path"`, `MFEK_MODULE_ORDER` overrides `"order"`, and e.g.
+    /// `MFEK_PIN_METADATA` pins `metadata`. See [`TrustPolicy`] for its `"trust"`. Manifests are
+    /// those in [`manifest_dirs`].
+    pub fn from_env() -> Self {
+        Self::from_vars(env::vars_os())
+    }
+
+    /// Like [`from_env`](Self::from_env), but with `MFEK_MODULE_PATH` and friends taken from `vars`
+    /// rather than our environment.
+    pub fn from_vars<K: Into<OsString>, V: Into<OsString>>(vars: impl IntoIterator<Item = (K, V)>) -> Self {
+        let vars: HashMap<OsString, OsString> = vars.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        let mut search = SearchPath::new();
+        search.manifests = manifests_in(&manifest_dirs());
+        let modules = config::section("modules");
+
+        if let Some(modules) = &modules {
+            if let Some(paths) = modules.get("path").and_then(|p| p.as_array()) {
+                search.extra = paths.iter().filter_map(|p| p.as_str()).map(PathBuf::from).collect();
+            }
+            if let Some(order) = modules.get("order").and_then(|o| o.as_str()) {
+                search = search.order_str(order);
+            }
+            if let Some(pinned) = modules.get("pinned").and_then(|p| p.as_object()) {
+                for (module, path) in pinned {
+                    if let Some(path) = path.as_str() {
+                        search = search.pin(module, path);
+                    }
+                }
+            }
+        }
+
+        search.trust = TrustPolicy::from_config(modules.as_ref());
+
+        if let Some(paths) = vars.get(OsStr::new("MFEK_MODULE_PATH")) {
+            let mut extra: Vec<PathBuf> = env::split_paths(paths).filter(|p| !p.as_os_str().is_empty()).collect();
+            extra.append(&mut search.extra);
+            search.extra = extra;
+        }
+        if let Some(order) = vars.get(OsStr::new("MFEK_MODULE_ORDER")).and_then(|o| o.to_str()) {
+            search = search.order_str(order);
+        }
+        for (var, path) in vars {
+            let var = var.to_string_lossy();
+            if let Some(module) = var.strip_prefix("MFEK_PIN_") {
+                search = search.pin(&module.to_lowercase(), path);
+            }
+        }
+
+        search
+    }
+
+    fn order_str(self, order: &str) -> Self {
+        match order.parse() {
+            Ok(order) => self.order(order),
+            Err(()) => {
+                log::warn!("Ignoring unknown module search order {:?}", order);
+                self
+            }
+        }
+    }
+
+    pub fn order(mut self, order: SearchOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn trust(mut self, trust: TrustPolicy) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    pub fn manifest(mut self, manifest: Manifest) -> Self {
+        self.manifests.push(manifest);
+        self
+    }
+
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.extra.push(dir.into());
+        self
+    }
+
+    pub fn pin(mut self, module: &str, path: impl Into<PathBuf>) -> Self {
+        self.pinned.insert(module.to_string(), path.into());
+        self
+    }
+
+    /// Every directory to search, in order.
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let bindir: Vec<PathBuf> = current_exe().map(|pb| vec![pb.parent().unwrap().to_owned()]).unwrap_or(vec![]);
+        let path: Vec<PathBuf> = env::var_os("PATH").map(|paths| env::split_paths(&paths).collect()).unwrap_or_default();
+        let (first, second) = match self.order {
+            SearchOrder::PathFirst => (path, bindir),
+            SearchOrder::BundleFirst => (bindir, path),
+        };
+        self.extra.iter().cloned().chain(first).chain(second).collect()
+    }
+}
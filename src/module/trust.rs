@@ -0,0 +1,112 @@
+use log;
+use sha2::{Digest as _, Sha256};
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config;
+
+/// Checks a module binary must pass before it's ever run. All are off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustPolicy {
+    /// Reject binaries which, or whose directory, anyone may write to.
+    pub reject_world_writable: bool,
+    /// Reject binaries owned by anyone but us or root.
+    pub require_owner: bool,
+    /// Ignore relative `PATH` entries, like `.` or an empty entry.
+    pub ignore_relative: bool,
+    /// If set, only run binaries whose SHA-256 (lowercase hex) is listed.
+    pub sha256_allow: Option<HashSet<String>>,
+}
+
+fn sha256_hex(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    let mut hex = String::new();
+    for b in hasher.finalize() {
+        write!(hex, "{:02x}", b).unwrap();
+    }
+    Ok(hex)
+}
+
+impl TrustPolicy {
+    /// Everything but the allow-list.
+    pub fn strict() -> Self {
+        TrustPolicy { reject_world_writable: true, require_owner: true, ignore_relative: true, sha256_allow: None }
+    }
+
+    pub fn allow_sha256(mut self, hex: &str) -> Self {
+        self.sha256_allow.get_or_insert_with(HashSet::new).insert(hex.trim().to_lowercase());
+        self
+    }
+
+    /// The `"trust"` object in the config file's `"modules"`, e.g. `{"require_owner": true,
+    /// "sha256": ["…"]}`, or `"strict"`; `MFEK_MODULE_TRUST=strict` in the environment overrides it.
+    pub(crate) fn from_config(modules: Option<&serde_json::Value>) -> Self {
+        if std::env::var("MFEK_MODULE_TRUST").map(|t| t == "strict").unwrap_or(false) {
+            return TrustPolicy::strict();
+        }
+        let trust = match modules.and_then(|m| m.get("trust")) {
+            Some(trust) => trust,
+            None => return TrustPolicy::default(),
+        };
+        if trust.as_str() == Some("strict") {
+            return TrustPolicy::strict();
+        }
+        let flag = |k: &str| trust.get(k).and_then(|v| v.as_bool()).unwrap_or(false);
+        let mut policy = TrustPolicy {
+            reject_world_writable: flag("reject_world_writable"),
+            require_owner: flag("require_owner"),
+            ignore_relative: flag("ignore_relative"),
+            sha256_allow: None,
+        };
+        if let Some(hashes) = trust.get("sha256").and_then(|h| h.as_array()) {
+            for hash in hashes.iter().filter_map(|h| h.as_str()) {
+                policy = policy.allow_sha256(hash);
+            }
+        }
+        policy
+    }
+
+    /// `Err` says why `binary` mustn't be run.
+    pub fn check(&self, binary: &Path) -> Result<(), String> {
+        if self.ignore_relative && binary.is_relative() {
+            return Err("in a relative search directory".to_string());
+        }
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+            let md = fs::metadata(binary).map_err(|e| e.to_string())?;
+            if self.reject_world_writable {
+                if md.mode() & 0o002 != 0 {
+                    return Err("world-writable".to_string());
+                }
+                let dir = match binary.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                let dmd = fs::metadata(dir).map_err(|e| e.to_string())?;
+                if dmd.mode() & 0o002 != 0 {
+                    return Err(format!("in world-writable directory {:?}", dir));
+                }
+            }
+            if self.require_owner {
+                let euid = unsafe { libc::geteuid() };
+                if md.uid() != euid && md.uid() != 0 {
+                    return Err(format!("owned by uid {}, neither us nor root", md.uid()));
+                }
+            }
+        }
+        if let Some(allow) = &self.sha256_allow {
+            let hash = sha256_hex(binary).map_err(|e| e.to_string())?;
+            if !allow.contains(&hash) {
+                return Err(format!("SHA-256 {} not in allow-list", hash));
+            }
+        }
+        log::trace!("{:?} passes trust policy {:?}", binary, self);
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//! Telling modules we start which mfek-ipc we were built with, so they can tell if theirs can talk
+//! to ours.
+//!
+//! Every command this library runs is [`stamp`]ed with our version and [`PROTOCOL`] in its
+//! environment. Modules should [`check`] (or [`refuse_incompatible`]) early in `main`.
+
+use log;
+
+use std::env;
+use std::ffi::OsStr;
+use std::process::Command;
+
+use crate::exit::ExitCode;
+use crate::module::Version;
+
+/// Bumped whenever a change to this library means modules built with it and modules built with
+/// the one before can no longer talk to each other, whatever the crate versions say.
+pub const PROTOCOL: u32 = 1;
+pub static VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub static VERSION_VAR: &str = "MFEK_IPC_VERSION";
+pub static PROTOCOL_VAR: &str = "MFEK_IPC_PROTOCOL";
+
+/// Puts our version and protocol in `command`'s environment.
+pub fn stamp(command: &mut Command) -> &mut Command {
+    command.env(VERSION_VAR, VERSION).env(PROTOCOL_VAR, PROTOCOL.to_string())
+}
+
+/// The mfek-ipc of whoever started us, as they stamped it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parent {
+    pub version: String,
+    /// `None` if it wasn't a number.
+    pub protocol: Option<u32>,
+}
+
+impl Parent {
+    /// As stamped in `vars`, e.g. those [`stamp`] set on a `Command`.
+    pub fn from_vars<K: AsRef<OsStr>, V: AsRef<OsStr>>(vars: impl IntoIterator<Item = (K, V)>) -> Option<Parent> {
+        let (mut version, mut protocol) = (None, None);
+        for (var, value) in vars {
+            let value = value.as_ref().to_str().map(str::to_string);
+            if var.as_ref() == VERSION_VAR {
+                version = value;
+            } else if var.as_ref() == PROTOCOL_VAR {
+                protocol = value.and_then(|p| p.trim().parse().ok());
+            }
+        }
+        Some(Parent { version: version?, protocol })
+    }
+}
+
+/// `None` if we weren't started by a module using this library (or one too old to stamp).
+pub fn parent() -> Option<Parent> {
+    Parent::from_vars(env::vars_os())
+}
+
+/// Whether our parent's mfek-ipc can talk to ours: `UpToDate` if it speaks our protocol (whatever
+/// its version), else `OutOfDate` with its version, having warned about it. `None` if there's no
+/// parent to check.
+pub fn check() -> Option<Version<'static>> {
+    check_parent(parent()?)
+}
+
+/// [`check`] a parent found some other way than our environment.
+pub fn check_parent(parent: Parent) -> Option<Version<'static>> {
+    if parent.protocol == Some(PROTOCOL) {
+        if parent.version != VERSION {
+            log::debug!("Parent has mfek-ipc {}, we have {}, but both speak protocol {}", parent.version, VERSION, PROTOCOL);
+        }
+        return Some(Version::UpToDate(VERSION));
+    }
+    let degraded = match parent.protocol {
+        Some(protocol) => format!("IPC protocol {}", protocol),
+        None => "no readable IPC protocol".to_string(),
+    };
+    log::warn!(
+        "Got {} from our parent's mfek-ipc {}. Talking to it may not work. Please update whichever of the two MFEK modules is older. (Expected IPC protocol {}, from mfek-ipc {}.)",
+        degraded,
+        parent.version,
+        PROTOCOL,
+        VERSION
+    );
+    Some(Version::OutOfDate(Some(parent.version)))
+}
+
+/// [`check`], exiting with [`ExitCode::VersionMismatch`] if the parent can't talk to us.
+pub fn refuse_incompatible() {
+    if let Some(Version::OutOfDate(_)) = check() {
+        log::error!("Refusing to run with an incompatible parent");
+        ExitCode::VersionMismatch.exit();
+    }
+}
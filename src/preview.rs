@@ -0,0 +1,130 @@
+//! Sharing the unsaved state of a glyph with sibling modules (strokers, path ops previewers…) as
+//! it's edited, rather than only once [`notifythread`](crate::notifythread) sees it saved.
+//!
+//! The editor [`PreviewPublisher::bind`]s for its glyph and calls
+//! [`publish`](PreviewPublisher::publish) on every edit; previewers
+//! [`PreviewSubscriber::connect`] to the same glyph. Each update is the whole `.glif`, numbered;
+//! a subscriber that sees a gap in the numbers missed some, and may [`resync`] if it'd rather have
+//! the latest than wait for the next edit.
+//!
+//! [`resync`]: PreviewSubscriber::resync
+
+use glifparser::{Glif, PointData};
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::lock;
+use crate::pubsub::{Publisher, Request, Subscriber};
+use crate::{runtime, IPCInfo};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Message {
+    seq: u64,
+    glif: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreviewUpdate<PD: PointData> {
+    /// Starts at 1, and goes up by one per published edit.
+    pub seq: u64,
+    /// Updates published since the last one this subscriber received, which it never saw.
+    pub missed: u64,
+    pub glif: Glif<PD>,
+}
+
+#[derive(Debug)]
+pub enum PreviewError {
+    /// The `IPCInfo` has no glyph (or font) to share.
+    NoTarget,
+    /// The publisher sent a `.glif` glifparser can't read.
+    Glif(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for PreviewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreviewError::NoTarget => write!(f, "nothing to preview, IPCInfo has no glyph or font"),
+            PreviewError::Glif(e) => write!(f, "bad .glif in preview: {}", e),
+            PreviewError::Io(e) => write!(f, "I/O error on preview socket: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PreviewError {}
+
+impl From<io::Error> for PreviewError {
+    fn from(e: io::Error) -> Self {
+        PreviewError::Io(e)
+    }
+}
+
+fn socket_path(info: &IPCInfo) -> Result<PathBuf, PreviewError> {
+    let target = lock::target(info).ok_or(PreviewError::NoTarget)?;
+    Ok(runtime::subdir("sockets")?.join(format!("preview-{}.sock", runtime::key(target))))
+}
+
+pub struct PreviewPublisher {
+    publisher: Publisher,
+    seq: u64,
+}
+
+impl PreviewPublisher {
+    /// Starts sharing the glyph of `info`. Fails if another process already shares it.
+    pub fn bind(info: &IPCInfo) -> Result<Self, PreviewError> {
+        Ok(PreviewPublisher { publisher: Publisher::bind(&socket_path(info)?)?, seq: 0 })
+    }
+
+    /// Sends `glif` to all subscribers, returning its sequence number.
+    pub fn publish<PD: PointData>(&mut self, glif: &Glif<PD>) -> Result<u64, PreviewError> {
+        let xml = glifparser::write(glif).map_err(|e| PreviewError::Glif(format!("{:?}", e)))?;
+        self.seq += 1;
+        self.publisher.publish(serde_json::to_string(&Message { seq: self.seq, glif: xml }).unwrap());
+        log::trace!("Published preview #{}", self.seq);
+        Ok(self.seq)
+    }
+}
+
+pub struct PreviewSubscriber {
+    subscriber: Subscriber,
+    last: u64,
+}
+
+impl PreviewSubscriber {
+    /// Follows the glyph of `info`, starting with its latest published state, if any.
+    pub fn connect(info: &IPCInfo) -> Result<Self, PreviewError> {
+        Ok(PreviewSubscriber { subscriber: Subscriber::connect(&socket_path(info)?)?, last: 0 })
+    }
+
+    /// Blocks for the next update; `Ok(None)` once the publisher has gone away. Updates older than
+    /// one already received (as can happen after a [`resync`](Self::resync)) are skipped.
+    pub fn recv<PD: PointData>(&mut self) -> Result<Option<PreviewUpdate<PD>>, PreviewError> {
+        loop {
+            let line = match self.subscriber.recv()? {
+                Some(line) => line,
+                None => return Ok(None),
+            };
+            let msg: Message = serde_json::from_str(&line).map_err(|e| PreviewError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+            if msg.seq <= self.last {
+                continue;
+            }
+            // what came before we subscribed doesn't count as missed
+            let missed = if self.last == 0 { 0 } else { msg.seq - self.last - 1 };
+            if missed > 0 {
+                log::debug!("Missed {} preview update(s) before #{}", missed, msg.seq);
+            }
+            let glif = glifparser::read(&msg.glif).map_err(|e| PreviewError::Glif(format!("{:?}", e)))?;
+            self.last = msg.seq;
+            return Ok(Some(PreviewUpdate { seq: msg.seq, missed, glif }));
+        }
+    }
+
+    /// Asks for the latest state to be sent again, e.g. after missing updates.
+    pub fn resync(&mut self) -> Result<(), PreviewError> {
+        Ok(self.subscriber.request(Request::Resync)?)
+    }
+}
@@ -0,0 +1,309 @@
+//! Progress reports from long-running modules, and asking them to stop.
+//!
+//! The parent makes a [`ProgressChannel`] and [`attach`](ProgressChannel::attach)es it to the
+//! module's command, which tells the module where to connect through `$MFEK_PROGRESS`. The module
+//! makes a [`ProgressReporter`], which sends JSON lines like `{"progress":0.4,"message":"…"}` back
+//! to the parent, and which it should check [`is_cancelled`](ProgressReporter::is_cancelled) now and
+//! then.
+//!
+//! ```no_run
+//! # use mfek_ipc::progress::ProgressChannel;
+//! let channel = ProgressChannel::new().unwrap();
+//! let mut child = channel.attach(&mut std::process::Command::new("MFEKexport")).spawn().unwrap();
+//! for p in channel.iter_until(&mut child) {
+//!     eprintln!("{:.0}% {}", p.progress * 100., p.message.as_deref().unwrap_or(""));
+//! }
+//! child.wait().unwrap();
+//! ```
+//!
+//! Without a parent listening (or off Unix) reports go nowhere, and nothing is ever cancelled.
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::iter;
+use std::process::{self, Child, Command};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::runtime;
+
+/// How often [`ProgressChannel::iter_until`] checks whether the module has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Where the module finds its [`ProgressChannel`].
+pub static PROGRESS_VAR: &str = "MFEK_PROGRESS";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    /// From 0 to 1.
+    pub progress: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Cancel {
+    cancel: bool,
+}
+
+type Writer = Box<dyn Write + Send>;
+
+#[derive(Default)]
+struct Shared {
+    writer: Mutex<Option<Writer>>,
+    cancelled: AtomicBool,
+    dropped: AtomicBool,
+}
+
+impl Shared {
+    fn send_cancel(writer: &mut Writer) {
+        let line = serde_json::to_string(&Cancel { cancel: true }).unwrap() + "\n";
+        if let Err(e) = writer.write_all(line.as_bytes()).and_then(|()| writer.flush()) {
+            log::debug!("Couldn't send cancel request: {:?}", e);
+        }
+    }
+
+    /// The module connected; `writer` talks to it.
+    fn connected(&self, mut writer: Writer) {
+        let mut slot = self.writer.lock().unwrap();
+        if self.cancelled.load(Ordering::SeqCst) {
+            Shared::send_cancel(&mut writer);
+        }
+        *slot = Some(writer);
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(writer) = self.writer.lock().unwrap().as_mut() {
+            Shared::send_cancel(writer);
+        }
+    }
+}
+
+fn socket_path() -> io::Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    Ok(runtime::subdir("sockets")?.join(format!("progress-{}-{}.sock", process::id(), n)))
+}
+
+#[cfg(target_family = "unix")]
+mod imp {
+    use super::*;
+
+    use std::fs;
+    use std::io::{BufRead as _, BufReader};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::mpsc::Sender;
+    use std::thread;
+
+    fn serve(listener: UnixListener, shared: Arc<Shared>, tx: Sender<Progress>) {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::error!("Error accepting progress connection: {:?}", e);
+                return;
+            }
+        };
+        if shared.dropped.load(Ordering::SeqCst) {
+            return;
+        }
+        match stream.try_clone() {
+            Ok(writer) => shared.connected(Box::new(writer)),
+            Err(e) => log::warn!("Can't send cancel requests on progress channel: {:?}", e),
+        }
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            match serde_json::from_str::<Progress>(&line) {
+                Ok(progress) => {
+                    if tx.send(progress).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => log::warn!("Malformed progress report {:?}: {}", &line, e),
+            }
+        }
+    }
+
+    pub(super) fn listen(path: &Path, shared: Arc<Shared>) -> io::Result<Receiver<Progress>> {
+        let listener = UnixListener::bind(path)?;
+        log::trace!("Listening for progress on {:?}", path);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || serve(listener, shared, tx));
+        Ok(rx)
+    }
+
+    /// Wakes the listener, should nobody have connected.
+    pub(super) fn wake(path: &Path) {
+        let _ = UnixStream::connect(path);
+    }
+
+    /// [`wake`]s the listener so it can see it was dropped.
+    pub(super) fn close(path: &Path) {
+        wake(path);
+        let _ = fs::remove_file(path);
+    }
+
+    pub(super) fn connect(path: &Path, cancelled: Arc<AtomicBool>) -> io::Result<Writer> {
+        let stream = UnixStream::connect(path)?;
+        let reader = stream.try_clone()?;
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                if let Ok(Cancel { cancel: true }) = serde_json::from_str(&line) {
+                    log::info!("Parent asked us to cancel");
+                    cancelled.store(true, Ordering::SeqCst);
+                }
+            }
+        });
+        Ok(Box::new(stream))
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+mod imp {
+    use super::*;
+
+    pub(super) fn listen(_path: &Path, _shared: Arc<Shared>) -> io::Result<Receiver<Progress>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "progress reporting needs Unix domain sockets"))
+    }
+
+    pub(super) fn wake(_path: &Path) {}
+
+    pub(super) fn close(_path: &Path) {}
+
+    pub(super) fn connect(_path: &Path, _cancelled: Arc<AtomicBool>) -> io::Result<Writer> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "progress reporting needs Unix domain sockets"))
+    }
+}
+
+/// The parent's end: progress from one module run.
+pub struct ProgressChannel {
+    path: PathBuf,
+    rx: Receiver<Progress>,
+    shared: Arc<Shared>,
+}
+
+impl ProgressChannel {
+    pub fn new() -> io::Result<Self> {
+        let path = socket_path()?;
+        let shared = Arc::new(Shared::default());
+        let rx = imp::listen(&path, Arc::clone(&shared))?;
+        Ok(ProgressChannel { path, rx, shared })
+    }
+
+    /// Tells the module `command` runs where to report to.
+    pub fn attach<'c>(&self, command: &'c mut Command) -> &'c mut Command {
+        command.env(PROGRESS_VAR, &self.path)
+    }
+
+    /// Reports as they come, until the module closes its end. A module that never connects never
+    /// closes it, so this never ends: use [`iter_until`](Self::iter_until) with modules that may
+    /// not report at all.
+    pub fn iter(&self) -> mpsc::Iter<'_, Progress> {
+        self.rx.iter()
+    }
+
+    /// Reports as they come, until `child` (the module this is attached to) has exited and every
+    /// report it sent has been read, whether or not it ever connected.
+    pub fn iter_until<'a>(&'a self, child: &'a mut Child) -> impl Iterator<Item = Progress> + 'a {
+        let mut exited = false;
+        iter::from_fn(move || loop {
+            if exited {
+                return self.rx.recv().ok();
+            }
+            match self.rx.recv_timeout(POLL_INTERVAL) {
+                Ok(progress) => return Some(progress),
+                Err(RecvTimeoutError::Disconnected) => return None,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if !matches!(child.try_wait(), Ok(None)) {
+                // it can't connect any more; if it never did, our connection ends the listener instead
+                exited = true;
+                imp::wake(&self.path);
+            }
+        })
+    }
+
+    /// Reports that have come so far.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, Progress> {
+        self.rx.try_iter()
+    }
+
+    /// Asks the module to stop. If it hasn't connected yet, it's asked as soon as it does.
+    pub fn cancel(&self) {
+        self.shared.cancel();
+    }
+
+    pub fn canceller(&self) -> Canceller {
+        Canceller(Arc::clone(&self.shared))
+    }
+}
+
+impl Drop for ProgressChannel {
+    fn drop(&mut self) {
+        self.shared.dropped.store(true, Ordering::SeqCst);
+        imp::close(&self.path);
+    }
+}
+
+/// [`ProgressChannel::cancel`], from another thread (e.g. the UI's).
+#[derive(Clone)]
+pub struct Canceller(Arc<Shared>);
+
+impl Canceller {
+    pub fn cancel(&self) {
+        self.0.cancel();
+    }
+}
+
+/// The module's end.
+pub struct ProgressReporter {
+    writer: Option<Writer>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ProgressReporter {
+    /// Connects to the channel in `$MFEK_PROGRESS`, if any.
+    pub fn from_env() -> Self {
+        match std::env::var_os(PROGRESS_VAR) {
+            Some(path) => Self::connect(Path::new(&path)),
+            None => ProgressReporter { writer: None, cancelled: Arc::new(AtomicBool::new(false)) },
+        }
+    }
+
+    pub fn connect(path: &Path) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let writer = match imp::connect(path, Arc::clone(&cancelled)) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                log::warn!("Can't report progress to {:?}: {:?}", path, e);
+                None
+            }
+        };
+        ProgressReporter { writer, cancelled }
+    }
+
+    pub fn report(&mut self, progress: f32, message: Option<&str>) {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return,
+        };
+        let line = serde_json::to_string(&Progress { progress, message: message.map(|m| m.to_string()) }).unwrap() + "\n";
+        if let Err(e) = writer.write_all(line.as_bytes()).and_then(|()| writer.flush()) {
+            log::debug!("Parent stopped listening for progress: {:?}", e);
+            self.writer = None;
+        }
+    }
+
+    /// Whether the parent has asked us to stop. Cheap enough to call in a loop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,299 @@
+//! One-to-many line-based publishing over a local socket, shared by [`preview`](crate::preview)
+//! and [`sync`](crate::sync).
+//!
+//! Each message is a JSON line carrying a full state, so a subscriber that missed some only ever
+//! needs the latest. A subscriber that falls behind has its oldest messages dropped rather than
+//! stalling the publisher, so it always gets the last; it can ask for the latest again with a
+//! resync request.
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How many messages may wait for a slow subscriber before the oldest are dropped for it.
+#[cfg(target_family = "unix")]
+const QUEUE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Request {
+    Resync,
+}
+
+#[derive(Default)]
+struct Shared {
+    latest: Mutex<Option<Arc<String>>>,
+    /// Set when the publisher is dropped, after which no subscriber is taken on.
+    stopped: AtomicBool,
+    #[cfg(target_family = "unix")]
+    subscribers: Mutex<Vec<Arc<imp::Outbox>>>,
+}
+
+pub(crate) struct Publisher {
+    path: PathBuf,
+    shared: Arc<Shared>,
+}
+
+pub(crate) struct Subscriber {
+    #[cfg(target_family = "unix")]
+    reader: io::BufReader<std::os::unix::net::UnixStream>,
+    #[cfg(target_family = "unix")]
+    writer: std::os::unix::net::UnixStream,
+}
+
+/// For another thread to make requests of a [`Subscriber`]'s publisher while it `recv`s, or close it.
+pub(crate) struct SubscriberHandle {
+    #[cfg(target_family = "unix")]
+    stream: std::os::unix::net::UnixStream,
+}
+
+#[cfg(target_family = "unix")]
+mod imp {
+    use super::*;
+
+    use std::collections::VecDeque;
+    use std::fs;
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::net::Shutdown;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::Condvar;
+    use std::thread;
+    use std::time::Duration;
+
+    /// How long a write to a subscriber that's stopped reading may block its writer thread.
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[derive(Default)]
+    struct Queue {
+        msgs: VecDeque<Arc<String>>,
+        /// No more messages, because the publisher or the subscriber is gone.
+        closed: bool,
+    }
+
+    /// What's waiting to be written to one subscriber.
+    #[derive(Default)]
+    pub(super) struct Outbox {
+        queue: Mutex<Queue>,
+        ready: Condvar,
+    }
+
+    impl Outbox {
+        /// Queues `msg`, dropping the oldest if the subscriber is behind; `false` if it's gone.
+        fn offer(&self, msg: Arc<String>) -> bool {
+            let mut queue = self.queue.lock().unwrap();
+            if queue.closed {
+                return false;
+            }
+            queue.msgs.push_back(msg);
+            if queue.msgs.len() > QUEUE {
+                log::trace!("Subscriber is behind, dropping its oldest message");
+                queue.msgs.pop_front();
+            }
+            self.ready.notify_one();
+            true
+        }
+
+        fn close(&self) {
+            self.queue.lock().unwrap().closed = true;
+            self.ready.notify_one();
+        }
+
+        /// The next message to write, `None` once closed and all written.
+        fn next(&self) -> Option<Arc<String>> {
+            let mut queue = self.queue.lock().unwrap();
+            loop {
+                if let Some(msg) = queue.msgs.pop_front() {
+                    return Some(msg);
+                }
+                if queue.closed {
+                    return None;
+                }
+                queue = self.ready.wait(queue).unwrap();
+            }
+        }
+    }
+
+    fn subscribe(shared: &Arc<Shared>, stream: UnixStream) -> io::Result<()> {
+        let outbox = Arc::new(Outbox::default());
+        let requests = BufReader::new(stream.try_clone()?);
+        let mut stream = stream;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+        // under the subscribers lock, so no publish can slip in between sending the latest and joining,
+        // and so we don't join a publisher that's being dropped
+        let mut subscribers = shared.subscribers.lock().unwrap();
+        if shared.stopped.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if let Some(latest) = shared.latest.lock().unwrap().clone() {
+            outbox.offer(latest);
+        }
+        subscribers.push(Arc::clone(&outbox));
+        drop(subscribers);
+
+        let writing = Arc::clone(&outbox);
+        thread::spawn(move || {
+            while let Some(msg) = writing.next() {
+                if stream.write_all(msg.as_bytes()).is_err() {
+                    writing.close();
+                    break;
+                }
+            }
+            // ends the subscriber's stream, and our reading of its requests
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+        let shared = Arc::clone(shared);
+        thread::spawn(move || {
+            for line in requests.lines() {
+                match line.map(|l| serde_json::from_str::<Request>(&l)) {
+                    Ok(Ok(Request::Resync)) => {
+                        if let Some(latest) = shared.latest.lock().unwrap().clone() {
+                            outbox.offer(latest);
+                        }
+                    }
+                    Ok(Err(e)) => log::warn!("Ignoring malformed subscriber request: {}", e),
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Wakes the accept loop so it can see the publisher was stopped, removes its socket, and closes
+    /// every subscriber once it's been sent what's queued for it.
+    pub(super) fn close(path: &Path, shared: &Shared) {
+        let _ = UnixStream::connect(path);
+        let _ = fs::remove_file(path);
+        for outbox in shared.subscribers.lock().unwrap().drain(..) {
+            outbox.close();
+        }
+    }
+
+    impl Publisher {
+        pub(crate) fn bind(path: &Path) -> io::Result<Publisher> {
+            if path.exists() {
+                if UnixStream::connect(path).is_ok() {
+                    return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{:?} already has a publisher", path)));
+                }
+                fs::remove_file(path)?;
+            }
+            let listener = UnixListener::bind(path)?;
+            let shared = Arc::new(Shared::default());
+            let accepting = Arc::clone(&shared);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if accepting.stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream.and_then(|s| subscribe(&accepting, s)) {
+                        Ok(()) => log::trace!("New subscriber"),
+                        Err(e) => log::error!("Error accepting subscriber: {:?}", e),
+                    }
+                }
+            });
+            log::debug!("Publishing on {:?}", path);
+            Ok(Publisher { path: path.to_path_buf(), shared })
+        }
+
+        /// Sends `msg` (one line of JSON, without the newline) to every subscriber.
+        pub(crate) fn publish(&self, msg: String) {
+            let msg = Arc::new(msg + "\n");
+            let mut subscribers = self.shared.subscribers.lock().unwrap();
+            *self.shared.latest.lock().unwrap() = Some(Arc::clone(&msg));
+            subscribers.retain(|outbox| outbox.offer(Arc::clone(&msg)));
+        }
+    }
+
+    impl Subscriber {
+        pub(crate) fn connect(path: &Path) -> io::Result<Subscriber> {
+            let writer = UnixStream::connect(path)?;
+            Ok(Subscriber { reader: BufReader::new(writer.try_clone()?), writer })
+        }
+
+        /// Blocks for the next message; `Ok(None)` once the publisher is gone.
+        pub(crate) fn recv(&mut self) -> io::Result<Option<String>> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line)),
+            }
+        }
+
+        pub(crate) fn request(&mut self, req: Request) -> io::Result<()> {
+            self.writer.write_all((serde_json::to_string(&req).unwrap() + "\n").as_bytes())
+        }
+
+        pub(crate) fn handle(&self) -> io::Result<SubscriberHandle> {
+            Ok(SubscriberHandle { stream: self.writer.try_clone()? })
+        }
+    }
+
+    impl SubscriberHandle {
+        pub(crate) fn request(&self, req: Request) -> io::Result<()> {
+            (&self.stream).write_all((serde_json::to_string(&req).unwrap() + "\n").as_bytes())
+        }
+
+        /// Ends the subscription; its `recv` then returns `Ok(None)`.
+        pub(crate) fn close(&self) {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+mod imp {
+    use super::*;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "publishing needs Unix domain sockets")
+    }
+
+    impl Publisher {
+        pub(crate) fn bind(_path: &Path) -> io::Result<Publisher> {
+            Err(unsupported())
+        }
+
+        pub(crate) fn publish(&self, msg: String) {
+            *self.shared.latest.lock().unwrap() = Some(Arc::new(msg + "\n"));
+        }
+    }
+
+    impl Subscriber {
+        pub(crate) fn connect(_path: &Path) -> io::Result<Subscriber> {
+            Err(unsupported())
+        }
+
+        pub(crate) fn recv(&mut self) -> io::Result<Option<String>> {
+            Err(unsupported())
+        }
+
+        pub(crate) fn request(&mut self, _req: Request) -> io::Result<()> {
+            Err(unsupported())
+        }
+
+        pub(crate) fn handle(&self) -> io::Result<SubscriberHandle> {
+            Err(unsupported())
+        }
+    }
+
+    impl SubscriberHandle {
+        pub(crate) fn request(&self, _req: Request) -> io::Result<()> {
+            Err(unsupported())
+        }
+
+        pub(crate) fn close(&self) {}
+    }
+
+    pub(super) fn close(_path: &Path, _shared: &Shared) {}
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        imp::close(&self.path, &self.shared);
+    }
+}
@@ -0,0 +1,136 @@
+//! Which MFEK modules are running right now, and what they have open.
+//!
+//! Each module [`register`]s at startup, which leaves a file in the MFEK runtime directory
+//! (`$XDG_RUNTIME_DIR/mfek/instances`) until the returned [`Registration`] is dropped. Entries of
+//! processes that died without deregistering are pruned whenever anyone looks.
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::{runtime, IPCInfo};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instance {
+    pub pid: u32,
+    /// [`IPCInfo::parent_module`] of the instance.
+    pub module: String,
+    pub version: String,
+    pub exe: PathBuf,
+    pub font: Option<PathBuf>,
+    pub glyph: Option<PathBuf>,
+}
+
+impl Instance {
+    fn new(info: &IPCInfo, version: &str) -> Self {
+        Instance {
+            pid: process::id(),
+            module: info.parent_module.clone(),
+            version: version.to_string(),
+            exe: info.parent_exe.clone(),
+            font: info.font.clone(),
+            glyph: info.glyph.clone(),
+        }
+    }
+
+    /// Whether this instance has `font` open (compared as canonical paths).
+    pub fn has_font(&self, font: &Path) -> bool {
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        self.font.as_deref().map(canonical) == Some(canonical(font))
+    }
+}
+
+/// This process' entry in the registry, removed on drop.
+#[derive(Debug)]
+pub struct Registration {
+    path: PathBuf,
+    instance: Instance,
+}
+
+fn write_entry(path: &Path, instance: &Instance) -> io::Result<()> {
+    // readers must never see half an entry
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_string(instance).unwrap())?;
+    fs::rename(&tmp, path)
+}
+
+/// Registers this process as running `version` of the module in `info`, with its font and glyph.
+pub fn register(info: &IPCInfo, version: &str) -> io::Result<Registration> {
+    let instance = Instance::new(info, version);
+    let path = runtime::subdir("instances")?.join(format!("{}.json", instance.pid));
+    write_entry(&path, &instance)?;
+    log::debug!("Registered running instance {:?} at {:?}", &instance, &path);
+    Ok(Registration { path, instance })
+}
+
+impl Registration {
+    /// Call when the module opens another font or glyph.
+    pub fn update(&mut self, info: &IPCInfo) -> io::Result<()> {
+        self.instance.font = info.font.clone();
+        self.instance.glyph = info.glyph.clone();
+        write_entry(&self.path, &self.instance)
+    }
+
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn deregister(self) {}
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            log::warn!("Failed to deregister running instance at {:?}: {:?}", &self.path, e);
+        }
+    }
+}
+
+/// All live registered instances, removing entries of dead processes as it goes.
+pub fn all_instances() -> Vec<Instance> {
+    let dir = match runtime::subdir("instances") {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::error!("No MFEK runtime directory, can't list running instances: {:?}", e);
+            return vec![];
+        }
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("Failed to read {:?}: {:?}", &dir, e);
+            return vec![];
+        }
+    };
+
+    let mut instances = vec![];
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().map(|e| e != "json").unwrap_or(true) {
+            continue;
+        }
+        let instance: Option<Instance> = fs::read_to_string(&path).ok().and_then(|data| serde_json::from_str(&data).ok());
+        match instance {
+            Some(instance) if runtime::alive(instance.pid) => instances.push(instance),
+            dead => {
+                log::debug!("Pruning dead instance {:?} ({:?})", &path, dead);
+                if let Err(e) = fs::remove_file(&path) {
+                    log::warn!("Failed to prune {:?}: {:?}", &path, e);
+                }
+            }
+        }
+    }
+    instances
+}
+
+/// Live instances, optionally only those of `module` and/or with `font` open.
+pub fn running_instances(module: Option<&str>, font: Option<&Path>) -> Vec<Instance> {
+    all_instances()
+        .into_iter()
+        .filter(|i| module.map(|m| i.module == m).unwrap_or(true))
+        .filter(|i| font.map(|f| i.has_font(f)).unwrap_or(true))
+        .collect()
+}
@@ -0,0 +1,277 @@
+//! Serving a module as a long-lived process instead of a one-shot CLI: line-delimited JSON-RPC 2.0
+//! on stdin/stdout, started by passing the module `--ipc-server`.
+//!
+//! ```no_run
+//! use mfek_ipc::rpc::{self, RpcError, Server};
+//!
+//! if rpc::requested() {
+//!     Server::new()
+//!         .handler("add", |(a, b): (i64, i64), _cancel| Ok::<_, RpcError>(a + b))
+//!         .serve_stdio()
+//!         .unwrap();
+//!     return;
+//! }
+//! ```
+//!
+//! Besides the module's own methods, every server understands `ping` (reply with our
+//! [`PROTOCOL`](crate::negotiate::PROTOCOL), so clients can tell it's a server at all), `shutdown`
+//! (answer outstanding requests, reply `null` and stop) and the `$/cancelRequest` notification (`{"id": …}`), which
+//! handlers see through their [`CancelToken`]. A request cancelled before its handler returns gets a
+//! [`REQUEST_CANCELLED`] error rather than its result.
+
+use log;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::negotiate;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// As in the Language Server Protocol.
+pub const REQUEST_CANCELLED: i64 = -32800;
+
+pub const SERVER_FLAG: &str = "--ipc-server";
+
+/// Whether the module was started with `--ipc-server`.
+pub fn requested() -> bool {
+    // not env::args(), which panics on the non-UTF-8 paths Unix allows
+    env::args_os().skip(1).any(|a| a == SERVER_FLAG)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+    Number(i64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    /// `None` for notifications, which get no response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Id>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Option<Id>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: Option<Id>, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(v) => (Some(v), None),
+            Err(e) => (None, Some(e)),
+        };
+        Response { jsonrpc: "2.0".to_string(), id, result, error }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError { code, message: message.into(), data: None }
+    }
+
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (JSON-RPC error {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Set once the client cancels the request; long-running handlers should poll it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+}
+
+type Handler = Box<dyn Fn(Value, &CancelToken) -> Result<Value, RpcError> + Send + Sync>;
+
+#[derive(Default)]
+pub struct Server {
+    handlers: HashMap<String, Handler>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CancelParams {
+    id: Id,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `f` for `method`. Its params are deserialized into `P` (an `INVALID_PARAMS` error if
+    /// they don't fit), and its result serialized back.
+    pub fn handler<P, R, F>(mut self, method: &str, f: F) -> Self
+    where
+        P: DeserializeOwned,
+        R: Serialize,
+        F: Fn(P, &CancelToken) -> Result<R, RpcError> + Send + Sync + 'static,
+    {
+        let handler = move |params: Value, cancel: &CancelToken| {
+            let params = serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+            let result = f(params, cancel)?;
+            serde_json::to_value(result).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+        };
+        self.handlers.insert(method.to_string(), Box::new(handler));
+        self
+    }
+
+    pub fn serve_stdio(&self) -> io::Result<()> {
+        let stdin = io::stdin();
+        self.serve(stdin.lock(), io::stdout())
+    }
+
+    /// Serves requests read from `input` until `shutdown` or end of input, each on its own thread so
+    /// they may be cancelled. Responses are written to `output` as they complete.
+    pub fn serve<R: BufRead, W: Write + Send>(&self, input: R, output: W) -> io::Result<()> {
+        let output = Mutex::new(output);
+        let in_flight: Mutex<HashMap<Id, CancelToken>> = Mutex::new(HashMap::new());
+        let respond = |response: Response| -> io::Result<()> {
+            let mut output = output.lock().unwrap();
+            writeln!(output, "{}", serde_json::to_string(&response).unwrap())?;
+            output.flush()
+        };
+
+        thread::scope(|scope| -> io::Result<Option<Option<Id>>> {
+            for line in input.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let request: Request = match serde_json::from_str::<Value>(&line) {
+                    Err(e) => {
+                        respond(Response::new(None, Err(RpcError::new(PARSE_ERROR, e.to_string()))))?;
+                        continue;
+                    }
+                    Ok(v) => match serde_json::from_value(v) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            respond(Response::new(None, Err(RpcError::new(INVALID_REQUEST, e.to_string()))))?;
+                            continue;
+                        }
+                    },
+                };
+                log::trace!("JSON-RPC request: {:?}", &request);
+                if request.jsonrpc != "2.0" {
+                    let e = RpcError::new(INVALID_REQUEST, format!("jsonrpc must be \"2.0\", not {:?}", &request.jsonrpc));
+                    respond(Response::new(request.id, Err(e)))?;
+                    continue;
+                }
+
+                match request.method.as_str() {
+                    "ping" => {
+                        if request.id.is_some() {
+                            respond(Response::new(request.id, Ok(Value::from(negotiate::PROTOCOL))))?;
+                        }
+                        continue;
+                    }
+                    "shutdown" => {
+                        log::debug!("JSON-RPC shutdown requested");
+                        return Ok(Some(request.id));
+                    }
+                    "$/cancelRequest" => {
+                        match serde_json::from_value::<CancelParams>(request.params) {
+                            Ok(CancelParams { id }) => match in_flight.lock().unwrap().get(&id) {
+                                Some(token) => token.cancel(),
+                                None => log::debug!("Cancel of unknown or finished request {:?}", id),
+                            },
+                            Err(e) => log::warn!("Malformed $/cancelRequest: {}", e),
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                let handler = match self.handlers.get(&request.method) {
+                    Some(h) => h,
+                    None => {
+                        if request.id.is_some() {
+                            let e = RpcError::new(METHOD_NOT_FOUND, format!("no method {:?}", &request.method));
+                            respond(Response::new(request.id, Err(e)))?;
+                        }
+                        continue;
+                    }
+                };
+                let cancel = CancelToken::default();
+                if let Some(id) = &request.id {
+                    in_flight.lock().unwrap().insert(id.clone(), cancel.clone());
+                }
+                let (respond, in_flight) = (&respond, &in_flight);
+                scope.spawn(move || {
+                    let params = request.params;
+                    // a panicking handler would otherwise take the whole server down when the scope joins
+                    let mut result = panic::catch_unwind(AssertUnwindSafe(|| handler(params, &cancel))).unwrap_or_else(|panic| {
+                        let what = panic.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| panic.downcast_ref::<String>().cloned());
+                        log::error!("JSON-RPC handler for {:?} panicked: {}", &request.method, what.as_deref().unwrap_or("?"));
+                        Err(RpcError::new(INTERNAL_ERROR, format!("handler panicked: {}", what.as_deref().unwrap_or("?"))))
+                    });
+                    if cancel.is_cancelled() {
+                        result = Err(RpcError::new(REQUEST_CANCELLED, "Request cancelled"));
+                    }
+                    if let Some(id) = request.id {
+                        in_flight.lock().unwrap().remove(&id);
+                        if let Err(e) = respond(Response::new(Some(id), result)) {
+                            log::error!("Failed to write JSON-RPC response: {:?}", e);
+                        }
+                    } else if let Err(e) = result {
+                        log::error!("JSON-RPC notification {:?} failed: {}", &request.method, e);
+                    }
+                });
+            }
+            Ok(None)
+        })
+        .and_then(|shutdown| match shutdown {
+            // the scope has joined every handler by now, so this is the last response
+            Some(id) => respond(Response::new(id, Ok(Value::Null))),
+            None => Ok(()),
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! Where running MFEK processes leave state for each other, and how they tell who's still alive.
+
+use log;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `$XDG_RUNTIME_DIR/mfek`, else `mfek-$USER` in the temporary directory. Created (private to the
+/// user) if missing, and refused if it's anybody else's.
+pub(crate) fn dir() -> io::Result<PathBuf> {
+    let dir = match env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        Some(xdg) => PathBuf::from(xdg).join("mfek"),
+        None => {
+            let user = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_else(|_| "user".to_string());
+            env::temp_dir().join(format!("mfek-{}", user))
+        }
+    };
+    ensure_private(&dir)?;
+    Ok(dir)
+}
+
+/// A directory inside [`dir`], created if missing.
+pub(crate) fn subdir(name: &str) -> io::Result<PathBuf> {
+    let sub = dir()?.join(name);
+    ensure(&sub)?;
+    Ok(sub)
+}
+
+fn ensure(dir: &Path) -> io::Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    log::debug!("Creating MFEK runtime directory {:?}", dir);
+    fs::create_dir_all(dir)?;
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Makes sure `dir` exists and is ours alone, as whoever can write to it can plant locks, sockets
+/// and `.glif`s for us to trust. In a shared temporary directory, another user may well have made
+/// it first.
+#[cfg(target_family = "unix")]
+fn ensure_private(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => log::debug!("Created MFEK runtime directory {:?}", dir),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    let meta = fs::symlink_metadata(dir)?;
+    let uid = unsafe { libc::geteuid() };
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o777 != 0o700 {
+        log::error!("MFEK runtime directory {:?} isn't private to us (uid {}, mode {:o}), refusing to use it", dir, meta.uid(), meta.mode() & 0o7777);
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("{:?} isn't a directory of uid {} with mode 700", dir, uid)));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn ensure_private(dir: &Path) -> io::Result<()> {
+    ensure(dir)
+}
+
+/// A stable (across processes, builds and Rust versions) name for a path, usable as a file name.
+/// FNV-1a, as `std`'s hashers make no such promise.
+pub(crate) fn key(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in path.to_string_lossy().bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Whether process `pid` still exists. Where we can't tell, assume it does, so nobody's state is
+/// ever thrown away while they're using it.
+#[cfg(target_family = "unix")]
+pub(crate) fn alive(pid: u32) -> bool {
+    // signal 0 checks for existence without delivering anything; EPERM means it exists but isn't ours
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM) }
+}
+
+#[cfg(not(target_family = "unix"))]
+pub(crate) fn alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,148 @@
+//! Running other modules to completion: with a timeout, killing them if they hang, and logging
+//! what they print on stderr rather than throwing it away.
+
+use log;
+
+use crate::exit::ExitCode;
+use crate::negotiate;
+
+use std::fmt;
+use std::io::{self, Read};
+use std::mem;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// For `--version` and the like, which should be instant.
+pub(crate) const QUICK_TIMEOUT: Duration = Duration::from_secs(5);
+/// For queries which read a font.
+pub(crate) const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long its pipes have to close after it exits, however near the timeout that was.
+const PIPE_GRACE: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub(crate) struct Output {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: ExitStatus,
+}
+
+#[derive(Debug)]
+pub(crate) enum RunError {
+    /// Couldn't start it at all, e.g. not found.
+    Spawn(io::Error),
+    /// Killed after running this long.
+    Timeout(Duration),
+    /// Exited unsuccessfully.
+    Failed(Output),
+    Io(io::Error),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Spawn(e) => write!(f, "failed to start: {}", e),
+            RunError::Timeout(t) => write!(f, "killed after timing out ({:?})", t),
+            RunError::Failed(o) => match ExitCode::check(o.status) {
+                Err(e) => write!(f, "exited unsuccessfully: {}", e),
+                Ok(()) => write!(f, "exited unsuccessfully ({})", o.status),
+            },
+            RunError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+/// A pipe read to its end on a thread of its own, which can be given up on.
+struct Drain {
+    buf: Arc<Mutex<Vec<u8>>>,
+    done: Receiver<io::Result<()>>,
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> Drain {
+    let buf = Arc::new(Mutex::new(vec![]));
+    let (tx, done) = mpsc::channel();
+    let filling = Arc::clone(&buf);
+    thread::spawn(move || {
+        let mut chunk = [0; 8192];
+        let res = loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) => break Ok(()),
+                Ok(n) => filling.lock().unwrap().extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = tx.send(res);
+    });
+    Drain { buf, done }
+}
+
+impl Drain {
+    /// What was read, waiting for the pipe to close until `deadline`. Past that, something the module
+    /// left running in the background holds it open, so make do with what we have.
+    fn finish(self, deadline: Instant, command: &Command) -> io::Result<Vec<u8>> {
+        match self.done.recv_timeout(deadline.saturating_duration_since(Instant::now()).max(PIPE_GRACE)) {
+            Ok(res) => res?,
+            Err(_) => log::warn!("{:?} exited, but something it left running holds its output open; not waiting for it", command.get_program()),
+        }
+        Ok(mem::take(&mut *self.buf.lock().unwrap()))
+    }
+}
+
+pub(crate) fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Runs `command` with no stdin, killing it if it takes longer than `timeout`. Its stderr is logged
+/// line by line: as warnings if it fails, else at debug level.
+pub(crate) fn run(command: &mut Command, timeout: Duration) -> Result<Output, RunError> {
+    negotiate::stamp(command);
+    log::trace!("Running {:?} (timeout {:?})", command, timeout);
+    let deadline = Instant::now() + timeout;
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().map_err(RunError::Spawn)?;
+    // read both pipes as it runs, lest it block on a full one
+    let stdout = drain(child.stdout.take().unwrap());
+    let stderr = drain(child.stderr.take().unwrap());
+
+    let status = match wait_timeout(&mut child, timeout).map_err(RunError::Io)? {
+        Some(status) => status,
+        None => {
+            log::error!("{:?} timed out after {:?}, killing it", command, timeout);
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(RunError::Timeout(timeout));
+        }
+    };
+    let output = Output {
+        stdout: stdout.finish(deadline, command).map_err(RunError::Io)?,
+        stderr: stderr.finish(deadline, command).map_err(RunError::Io)?,
+        status,
+    };
+
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        if status.success() {
+            log::debug!("{:?}: {}", command.get_program(), line);
+        } else {
+            log::warn!("{:?}: {}", command.get_program(), line);
+        }
+    }
+    if status.success() {
+        Ok(output)
+    } else {
+        log::error!("{:?} exited unsuccessfully ({})", command, status);
+        Err(RunError::Failed(output))
+    }
+}
@@ -0,0 +1,321 @@
+//! Starting modules so they don't outlive us, reaping them when they exit, and restarting the
+//! long-lived ones that die.
+//!
+//! Each module gets its own process group, so that on [`stop`](Supervisor::stop) (or when the
+//! [`Supervisor`] is dropped) anything it started goes too. On Linux each also gets `SIGTERM` should
+//! we die without cleaning up, by `PR_SET_PDEATHSIG`. As that fires when the *thread* that started
+//! it exits, modules are started (and restarted) on the supervisor's own thread, which lives as long
+//! as the supervisor does, whichever thread calls [`spawn`](Supervisor::spawn).
+
+use log;
+
+use crate::module::{self, ModuleError, SearchPath};
+use crate::negotiate;
+
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const REAP_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a module has to exit on `SIGTERM` before it's killed.
+const TERM_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    Never,
+    /// Restart it whenever it exits unsuccessfully, at most `max_restarts` times, waiting `backoff`
+    /// first, doubled on each restart up to `max_backoff`.
+    OnFailure { max_restarts: u32, backoff: Duration, max_backoff: Duration },
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy::Never
+    }
+}
+
+impl RestartPolicy {
+    /// Five restarts, waiting 100ms, then 200ms…
+    pub fn on_failure() -> Self {
+        RestartPolicy::OnFailure { max_restarts: 5, backoff: Duration::from_millis(100), max_backoff: Duration::from_secs(10) }
+    }
+
+    /// How long to wait before restart number `restarts + 1`, if there is to be one.
+    fn delay(&self, restarts: u32) -> Option<Duration> {
+        match *self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnFailure { max_restarts, .. } if restarts >= max_restarts => None,
+            RestartPolicy::OnFailure { backoff, max_backoff, .. } => Some(backoff.saturating_mul(1 << restarts.min(16)).min(max_backoff)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChildState {
+    Running { pid: u32 },
+    /// Exited unsuccessfully; to be started again at `at`.
+    Restarting { at: Instant },
+    /// Exited, and won't be restarted.
+    Exited(ExitStatus),
+    /// By [`Supervisor::stop`].
+    Stopped,
+}
+
+/// Which module of a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChildId(usize);
+
+#[derive(Debug)]
+pub enum SupervisorError {
+    Module(ModuleError),
+    Spawn(io::Error),
+    NoSuchChild,
+}
+
+impl fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SupervisorError::Module(e) => write!(f, "{}", e),
+            SupervisorError::Spawn(e) => write!(f, "failed to start module: {}", e),
+            SupervisorError::NoSuchChild => write!(f, "no such supervised module"),
+        }
+    }
+}
+
+impl std::error::Error for SupervisorError {}
+
+impl From<ModuleError> for SupervisorError {
+    fn from(e: ModuleError) -> Self {
+        SupervisorError::Module(e)
+    }
+}
+
+#[derive(Debug)]
+struct Supervised {
+    path: PathBuf,
+    args: Vec<OsString>,
+    policy: RestartPolicy,
+    child: Option<Child>,
+    state: ChildState,
+    restarts: u32,
+    last_status: Option<ExitStatus>,
+}
+
+fn command(path: &Path, args: &[OsString]) -> Command {
+    let mut command = Command::new(path);
+    negotiate::stamp(&mut command).args(args);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::CommandExt as _;
+        command.process_group(0);
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt as _;
+        let parent = std::process::id();
+        unsafe {
+            command.pre_exec(move || {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                // we may have died before the prctl took effect
+                if libc::getppid() as u32 != parent {
+                    libc::raise(libc::SIGTERM);
+                }
+                Ok(())
+            });
+        }
+    }
+    command
+}
+
+/// `SIGTERM` to its whole process group, then `SIGKILL` if it's still there after [`TERM_GRACE`].
+fn terminate(child: &mut Child) {
+    #[cfg(target_family = "unix")]
+    {
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) };
+        let deadline = Instant::now() + TERM_GRACE;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            thread::sleep(REAP_INTERVAL);
+        }
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+impl Supervised {
+    fn start(&mut self) -> io::Result<()> {
+        let child = command(&self.path, &self.args).spawn()?;
+        log::debug!("Started {:?} (pid {})", &self.path, child.id());
+        self.state = ChildState::Running { pid: child.id() };
+        self.child = Some(child);
+        Ok(())
+    }
+
+    /// After it's exited or failed to start, decides whether it'll be started again.
+    fn failed(&mut self) {
+        let status = self.last_status.expect("only restarted after exiting");
+        match self.policy.delay(self.restarts) {
+            Some(delay) if !status.success() => {
+                log::warn!("{:?} exited unsuccessfully ({}), restarting it in {:?}", &self.path, status, delay);
+                self.state = ChildState::Restarting { at: Instant::now() + delay };
+            }
+            _ => {
+                if !status.success() {
+                    log::error!("{:?} exited unsuccessfully ({})", &self.path, status);
+                }
+                self.state = ChildState::Exited(status);
+            }
+        }
+    }
+
+    fn reap(&mut self) {
+        match self.state {
+            ChildState::Running { .. } => {
+                let status = match self.child.as_mut().map(|c| c.try_wait()) {
+                    Some(Ok(Some(status))) => status,
+                    _ => return,
+                };
+                self.child = None;
+                self.last_status = Some(status);
+                self.failed();
+            }
+            ChildState::Restarting { at } if Instant::now() >= at => {
+                self.restarts += 1;
+                if let Err(e) = self.start() {
+                    log::error!("Failed to restart {:?}: {}", &self.path, e);
+                    self.failed();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A module for the reaper thread to start, and where to say how that went.
+type SpawnRequest = (Supervised, Sender<io::Result<ChildId>>);
+
+/// Modules we've started. Dropping it stops them all.
+pub struct Supervisor {
+    children: Arc<Mutex<Vec<Supervised>>>,
+    spawner: Mutex<Sender<SpawnRequest>>,
+    running: Arc<AtomicBool>,
+}
+
+/// Starts the modules asked for, and reaps (and restarts) those that exit.
+fn reaper(children: Arc<Mutex<Vec<Supervised>>>, running: Arc<AtomicBool>, requests: Receiver<SpawnRequest>) {
+    while running.load(Ordering::Relaxed) {
+        match requests.recv_timeout(REAP_INTERVAL) {
+            Ok((mut supervised, reply)) => {
+                let started = supervised.start().map(|()| {
+                    let mut children = children.lock().unwrap();
+                    children.push(supervised);
+                    ChildId(children.len() - 1)
+                });
+                let _ = reply.send(started);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        for child in children.lock().unwrap().iter_mut() {
+            child.reap();
+        }
+    }
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        let children = Arc::new(Mutex::new(vec![]));
+        let running = Arc::new(AtomicBool::new(true));
+        let (spawner, requests) = mpsc::channel();
+        let (reaping, still_running) = (Arc::clone(&children), Arc::clone(&running));
+        thread::spawn(move || reaper(reaping, still_running, requests));
+        Supervisor { children, spawner: Mutex::new(spawner), running }
+    }
+
+    /// Starts `module`, found by [`module::available`], with `args`. A module of another version
+    /// than `version` is started all the same, with the warning `available` gives.
+    pub fn spawn<S: Into<OsString>>(
+        &self,
+        module: &str,
+        version: &str,
+        args: impl IntoIterator<Item = S>,
+        policy: RestartPolicy,
+    ) -> Result<ChildId, SupervisorError> {
+        self.spawn_in(&SearchPath::from_env(), module, version, args, policy)
+    }
+
+    pub fn spawn_in<S: Into<OsString>>(
+        &self,
+        search: &SearchPath,
+        module: &str,
+        version: &str,
+        args: impl IntoIterator<Item = S>,
+        policy: RestartPolicy,
+    ) -> Result<ChildId, SupervisorError> {
+        let (_, path) = module::available_in(search, module, version).map_err(|()| ModuleError::Missing(module.to_string()))?;
+        let supervised = Supervised {
+            path,
+            args: args.into_iter().map(Into::into).collect(),
+            policy,
+            child: None,
+            state: ChildState::Stopped,
+            restarts: 0,
+            last_status: None,
+        };
+        let gone = || SupervisorError::Spawn(io::Error::new(io::ErrorKind::Other, "supervisor thread is gone"));
+        let (reply, started) = mpsc::channel();
+        self.spawner.lock().unwrap().send((supervised, reply)).map_err(|_| gone())?;
+        started.recv().map_err(|_| gone())?.map_err(SupervisorError::Spawn)
+    }
+
+    pub fn state(&self, id: ChildId) -> Option<ChildState> {
+        self.children.lock().unwrap().get(id.0).map(|c| c.state)
+    }
+
+    /// How many times it's been restarted.
+    pub fn restarts(&self, id: ChildId) -> Option<u32> {
+        self.children.lock().unwrap().get(id.0).map(|c| c.restarts)
+    }
+
+    /// Terminates it and everything it started, and won't restart it.
+    pub fn stop(&self, id: ChildId) -> Result<(), SupervisorError> {
+        let mut children = self.children.lock().unwrap();
+        let supervised = children.get_mut(id.0).ok_or(SupervisorError::NoSuchChild)?;
+        if let Some(mut child) = supervised.child.take() {
+            terminate(&mut child);
+        }
+        supervised.state = ChildState::Stopped;
+        Ok(())
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        let mut children = self.children.lock().unwrap();
+        for supervised in children.iter_mut() {
+            if let Some(mut child) = supervised.child.take() {
+                terminate(&mut child);
+            }
+            supervised.state = ChildState::Stopped;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Selection and viewport sync between modules showing the same glyph, e.g. MFEKglif and a
+//! preview module, so their views can follow each other.
+//!
+//! Every module showing the glyph [`join`](SyncChannel::join)s its channel, publishing its own state
+//! on a local socket and following every other member's. Members find each other by their sockets
+//! in the MFEK runtime directory, so they may come and go in any order.
+//!
+//! A module should only publish changes its user made; republishing what it was sent would echo
+//! between members forever.
+
+use log;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::lock;
+use crate::pubsub::{Publisher, Request, Subscriber, SubscriberHandle};
+use crate::{runtime, IPCInfo};
+
+/// How often to look for members who joined after us.
+const DISCOVERY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A point, by its place in the glifparser outline, and its UFO identifier if it has one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointRef {
+    pub contour: usize,
+    pub point: usize,
+    pub identifier: Option<String>,
+}
+
+/// The visible rectangle, in glyph units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// All of one member's view state; each message carries the whole of it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncState {
+    pub selection: Vec<PointRef>,
+    pub viewport: Option<Viewport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncMessage {
+    pub from_pid: u32,
+    pub state: SyncState,
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    /// The `IPCInfo` has no glyph (or font) to sync on.
+    NoTarget,
+    Io(io::Error),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncError::NoTarget => write!(f, "nothing to sync, IPCInfo has no glyph or font"),
+            SyncError::Io(e) => write!(f, "I/O error on sync socket: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<io::Error> for SyncError {
+    fn from(e: io::Error) -> Self {
+        SyncError::Io(e)
+    }
+}
+
+pub struct SyncChannel {
+    publisher: Publisher,
+    state: SyncState,
+    joined: Arc<AtomicBool>,
+    /// One per member followed, to resync or stop following it.
+    followers: Arc<Mutex<Vec<SubscriberHandle>>>,
+}
+
+fn follow(path: PathBuf, mut subscriber: Subscriber, tx: Sender<SyncMessage>) {
+    while let Ok(Some(line)) = subscriber.recv() {
+        match serde_json::from_str::<SyncMessage>(&line) {
+            Ok(msg) => {
+                if tx.send(msg).is_err() {
+                    return;
+                }
+            }
+            Err(e) => log::warn!("Ignoring malformed sync message: {}", e),
+        }
+    }
+    log::debug!("Sync member {:?} left", &path);
+}
+
+/// Connects to each member of the channel `prefix` once, as they appear, until `joined` is unset.
+fn discover(dir: PathBuf, prefix: String, ours: PathBuf, tx: Sender<SyncMessage>, joined: Arc<AtomicBool>, followers: Arc<Mutex<Vec<SubscriberHandle>>>) {
+    let mut known = vec![ours];
+    while joined.load(Ordering::Relaxed) {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                let name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                let pid = match name.strip_prefix(&prefix).and_then(|n| n.strip_suffix(".sock")).and_then(|n| n.parse().ok()) {
+                    Some(pid) => pid,
+                    None => continue,
+                };
+                if known.contains(&path) {
+                    continue;
+                }
+                if !runtime::alive(pid) {
+                    log::debug!("Removing socket {:?} of dead sync member", &path);
+                    let _ = fs::remove_file(&path);
+                    continue;
+                }
+                known.push(path.clone());
+                let subscriber = match Subscriber::connect(&path).and_then(|s| Ok((s.handle()?, s))) {
+                    Ok((handle, subscriber)) => {
+                        // under the lock, so that dropping the channel can't miss it
+                        let mut followers = followers.lock().unwrap();
+                        if !joined.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        followers.push(handle);
+                        subscriber
+                    }
+                    Err(e) => {
+                        log::debug!("Couldn't follow sync member {:?}: {:?}", &path, e);
+                        continue;
+                    }
+                };
+                let tx = tx.clone();
+                thread::spawn(move || follow(path, subscriber, tx));
+            }
+        }
+        thread::sleep(DISCOVERY_INTERVAL);
+    }
+}
+
+impl SyncChannel {
+    /// Joins the channel for the glyph (or font) of `info`. Other members' states arrive on `tx`.
+    pub fn join(info: &IPCInfo, tx: Sender<SyncMessage>) -> Result<Self, SyncError> {
+        let target = lock::target(info).ok_or(SyncError::NoTarget)?;
+        let dir = runtime::subdir("sockets")?;
+        let prefix = format!("sync-{}-", runtime::key(target));
+        let ours = dir.join(format!("{}{}.sock", prefix, process::id()));
+        let publisher = Publisher::bind(&ours)?;
+        let joined = Arc::new(AtomicBool::new(true));
+        let followers = Arc::new(Mutex::new(vec![]));
+        let (discovering, following) = (Arc::clone(&joined), Arc::clone(&followers));
+        thread::spawn(move || discover(dir, prefix, ours, tx, discovering, following));
+        Ok(SyncChannel { publisher, state: SyncState::default(), joined, followers })
+    }
+
+    /// Asks every other member to send its state again, e.g. if this one's receiver fell behind.
+    /// Their states arrive on `tx` as usual.
+    pub fn resync(&self) {
+        // a member that's gone can't be written to, and needn't be asked again
+        self.followers.lock().unwrap().retain(|member| member.request(Request::Resync).is_ok());
+    }
+
+    pub fn set_selection(&mut self, selection: Vec<PointRef>) {
+        self.state.selection = selection;
+        self.publish();
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.state.viewport = Some(viewport);
+        self.publish();
+    }
+
+    pub fn state(&self) -> &SyncState {
+        &self.state
+    }
+
+    fn publish(&self) {
+        let msg = SyncMessage { from_pid: process::id(), state: self.state.clone() };
+        self.publisher.publish(serde_json::to_string(&msg).unwrap());
+    }
+}
+
+impl Drop for SyncChannel {
+    fn drop(&mut self) {
+        self.joined.store(false, Ordering::Relaxed);
+        for member in self.followers.lock().unwrap().drain(..) {
+            member.close();
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! What test binaries needing a runtime directory of their own, or a second process, share.
+#![allow(dead_code)]
+
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+use std::sync::Once;
+use std::{env, fs};
+
+const PREFIX: &str = "mfek-ipc-test-";
+/// Set, to what it needs, when a test binary runs one of its own [`helper`]s.
+const HELPER_VAR: &str = "MFEK_IPC_TEST_HELPER";
+
+/// `mfek-ipc-test-<binary>-<pid>` in the temporary directory.
+pub fn runtime_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let stem = exe.file_stem().unwrap().to_string_lossy().to_string();
+    // less the hash cargo appends
+    let binary = stem.split('-').next().unwrap().to_string();
+    env::temp_dir().join(format!("{}{}-{}", PREFIX, binary, process::id()))
+}
+
+/// Points `XDG_RUNTIME_DIR` at [`runtime_dir`], first removing those of earlier runs. Does nothing
+/// in a [`helper`], which shares its parent's.
+pub fn setup() {
+    static SETUP: Once = Once::new();
+    SETUP.call_once(|| {
+        if helper_arg().is_some() {
+            return;
+        }
+        remove_stale();
+        env::set_var("XDG_RUNTIME_DIR", runtime_dir());
+    });
+}
+
+/// A test binary can't clean up after its last test, so the next run does, of any whose process
+/// is gone.
+fn remove_stale() {
+    let entries = match fs::read_dir(env::temp_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let pid = match name.strip_prefix(PREFIX).and_then(|n| n.rsplit('-').next()).and_then(|pid| pid.parse::<u32>().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        if pid != process::id() && !alive(pid) {
+            let _ = fs::remove_dir_all(&path);
+        }
+    }
+}
+
+/// By kill(1), as libc isn't a dev-dependency. Where that can't tell, it's alive.
+fn alive(pid: u32) -> bool {
+    Command::new("kill").args(["-0", &pid.to_string()]).stderr(Stdio::null()).status().map_or(true, |s| s.success())
+}
+
+/// Runs the `#[ignore]`d test `name` of this same binary in a child process, for tests needing
+/// another pid. It gets `arg` from [`helper_arg`], and its parent's runtime directory.
+pub fn helper(name: &str, arg: impl AsRef<OsStr>) -> Command {
+    let mut command = Command::new(env::current_exe().unwrap());
+    command.args(["--exact", name, "--ignored", "--nocapture", "--test-threads=1"]).env(HELPER_VAR, arg);
+    command
+}
+
+/// What a [`helper`] was given. `None` when it's run as a test (e.g. by `--include-ignored`),
+/// which it should then do nothing as.
+pub fn helper_arg() -> Option<OsString> {
+    env::var_os(HELPER_VAR)
+}
@@ -0,0 +1,368 @@
+#![cfg(target_family = "unix")]
+//! The helpers, run against fake modules that record how they were run and answer with a copy of
+//! `test_data/glyphs/l.glif`.
+
+use glifparser::Glif;
+use mfek_ipc::helpers::init::{self, FontOptions};
+use mfek_ipc::helpers::metadata::MetadataSession;
+use mfek_ipc::exit::{ExitCode, ExitError};
+use mfek_ipc::helpers::pathops;
+use mfek_ipc::helpers::HelperError;
+use mfek_ipc::module::{self, KnownModule, SearchPath, Version};
+use mfek_ipc::helpers::stroke::{self, CapType, ConstantWidth, JoinType, PatternAlongPath, PatternCopies, Variable};
+use mfek_ipc::IPCInfo;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+const GLIF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/glyphs/l.glif");
+
+/// Writes its arguments, one a line, to `<the file after -i, or else -o>.argv`.
+const FAKE: &str = r#"#!/bin/sh
+[ "$1" = --version ] && echo "$(basename "$0") VERSION" && exit 0
+prev=; in=; out=
+for a in "$@"; do
+    case "$prev" in -i) in=$a;; -o) out=$a;; esac
+    prev=$a
+done
+printf '%s\n' "$@" > "${in:-$out}.argv"
+case "$in" in
+    *hang*) exec sleep 30;;
+    *fail*) echo "no good" >&2; exit 65;;
+    *noisy*) yes noise | head -n 100000 >&2;;
+    *orphan*) sleep 30 &;;
+esac
+case "$1" in
+    ufo) mkdir -p "$out";;
+    *) mkdir -p "$(dirname "$out")" && cp "GLIF" "$out";;
+esac
+"#;
+
+/// Serves `--ipc-server` (or not, for a font named `noserver`), writing a line to
+/// `<font>.starts` each time it's started. One named `stubborn` won't exit when asked to.
+const FAKE_METADATA: &str = r#"#!/bin/sh
+[ "$1" = --version ] && echo "MFEKmetadata VERSION" && exit 0
+font=$1
+if [ "$2" != --ipc-server ]; then
+    echo once >> "$font.starts"
+    printf '800\n-200\n'
+    exit 0
+fi
+echo server >> "$font.starts"
+case "$font" in *noserver*) exit 64;; esac
+while read -r line; do
+    id=$(printf '%s\n' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    case "$line" in
+        *'"ping"'*) printf '{"jsonrpc":"2.0","id":%s,"result":PROTOCOL}\n' "$id";;
+        *'"shutdown"'*)
+            case "$font" in *stubborn*) echo "not shutting down" >&2; exec sleep 30;; esac
+            printf '{"jsonrpc":"2.0","id":%s,"result":null}\n' "$id"; exit 0;;
+        *'"query"'*)
+            case "$font" in *slow*) exec sleep 30;; esac
+            printf '{"jsonrpc":"2.0","id":%s,"result":"800\\n-200"}\n' "$id"
+            case "$font" in *onequery*) exit 0;; esac;;
+    esac
+done
+"#;
+
+/// Everything logged, so that what modules print on stderr can be checked for.
+struct Logs(Mutex<Vec<(log::Level, String)>>);
+
+impl log::Log for Logs {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGS: Logs = Logs(Mutex::new(vec![]));
+
+fn logged(level: log::Level, containing: &str) -> bool {
+    LOGS.0.lock().unwrap().iter().any(|(l, message)| *l == level && message.contains(containing))
+}
+
+/// Pins every helper's module to a fake, before anything looks for them.
+fn setup() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        log::set_logger(&LOGS).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let dir = env::temp_dir().join(format!("mfek-ipc-test-fake-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        env::set_var("XDG_RUNTIME_DIR", dir.join("runtime"));
+        for module in ["init", "pathops", "stroke"] {
+            let bin = dir.join(format!("MFEK{}", module));
+            fs::write(&bin, FAKE.replace("VERSION", env!("CARGO_PKG_VERSION")).replace("GLIF", GLIF)).unwrap();
+            fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+            env::set_var(format!("MFEK_PIN_{}", module.to_uppercase()), &bin);
+        }
+        let bin = dir.join("MFEKmetadata");
+        let fake = FAKE_METADATA.replace("VERSION", env!("CARGO_PKG_VERSION")).replace("PROTOCOL", &mfek_ipc::negotiate::PROTOCOL.to_string());
+        fs::write(&bin, fake).unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        env::set_var("MFEK_PIN_METADATA", &bin);
+        // newer than any minimum
+        let bin = dir.join("MFEKglif");
+        fs::write(&bin, "#!/bin/sh\necho MFEKglif 99.0.0\n").unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        env::set_var("MFEK_PIN_GLIF", &bin);
+        dir
+    })
+}
+
+/// A copy of `l.glif` of its own for the test `name`.
+fn glyph(name: &str) -> (IPCInfo, PathBuf) {
+    let path = setup().join(format!("{}.glif", name));
+    fs::copy(GLIF, &path).unwrap();
+    (IPCInfo::from_glif_path("test".to_string(), &path), path)
+}
+
+fn argv(of: &Path) -> Vec<String> {
+    let mut file = of.as_os_str().to_owned();
+    file.push(".argv");
+    fs::read_to_string(file).unwrap().lines().map(str::to_string).collect()
+}
+
+fn l() -> Glif<()> {
+    glifparser::read(&fs::read_to_string(GLIF).unwrap()).unwrap()
+}
+
+#[test]
+fn stroke_constant_width() {
+    let (info, path) = glyph("stroke-cws");
+    let mut cws = ConstantWidth::new(20.);
+    cws.join = JoinType::Miter;
+    cws.end_cap = CapType::Square;
+    cws.remove_internal = true;
+    let stroked: Glif<()> = stroke::stroke(&info, cws).unwrap();
+    assert_eq!(stroked.name, l().name);
+
+    let argv = argv(&path);
+    assert_eq!(argv[..3], ["CWS", "-i", path.to_str().unwrap()]);
+    assert_eq!(argv[3], "-o");
+    assert!(argv[4].ends_with("output.glif"));
+    assert_eq!(argv[5..], ["--width", "20", "--jointype", "miter", "--startcap", "round", "--endcap", "square", "--remove-internal"]);
+    // the scratch directory is gone
+    assert!(!Path::new(&argv[4]).parent().unwrap().exists());
+}
+
+#[test]
+fn stroke_variable_and_pattern() {
+    let (info, path) = glyph("stroke-vws");
+    let _: Glif<()> = stroke::stroke(&info, Variable { join: JoinType::Bevel, ..Variable::default() }).unwrap();
+    assert_eq!(argv(&path)[0], "VWS");
+    assert_eq!(argv(&path)[5..], ["--jointype", "bevel", "--startcap", "round", "--endcap", "round"]);
+
+    let (info, path) = glyph("stroke-pap");
+    let mut pap = PatternAlongPath::new(GLIF);
+    pap.copies = PatternCopies::Repeated;
+    pap.scale = (0.5, 2.);
+    pap.spacing = 10.;
+    pap.center = true;
+    let _: Glif<()> = stroke::stroke(&info, pap).unwrap();
+    let argv = argv(&path);
+    assert_eq!(argv[0], "PAP");
+    assert_eq!(
+        argv[5..],
+        [
+            "--mode",
+            "repeated",
+            "--sx",
+            "0.5",
+            "--sy",
+            "2",
+            "--spacing",
+            "10",
+            "--normal-offset",
+            "0",
+            "--tangent-offset",
+            "0",
+            "--center-pattern",
+            "--pattern",
+            GLIF
+        ]
+    );
+}
+
+#[test]
+fn stroke_glif_in_memory() {
+    setup();
+    let stroked: Glif<()> = stroke::stroke(&l(), ConstantWidth::new(5.)).unwrap();
+    assert_eq!(stroked.name, l().name);
+}
+
+#[test]
+fn init_font_and_glyph() {
+    let font = setup().join("init.ufo");
+    let _ = fs::remove_dir_all(&font);
+    let info = init::new_font("glif", &font, &FontOptions::default().upm(2048).family("Test").layer("sketches")).unwrap();
+    assert_eq!(info.parent_module, "glif");
+    assert_eq!(info.font.as_deref(), Some(font.as_path()));
+    assert_eq!(argv(&font), ["ufo", "-o", font.to_str().unwrap(), "--upm", "2048", "--family", "Test", "--layer", "sketches"]);
+
+    let glyph = init::new_glyph(&info, "Aring", &['Å', 'A']).unwrap();
+    let path = font.join("glyphs/A_ring.glif");
+    assert_eq!(glyph.parent_module, "glif");
+    assert_eq!(glyph.glyph.as_deref(), Some(path.as_path()));
+    assert_eq!(argv(&path), ["glif", "-o", path.to_str().unwrap(), "-n", "Aring", "-u", "00C5", "-u", "0041"]);
+}
+
+#[test]
+fn pathops_boolean() {
+    let (info, path) = glyph("pathops-overlap");
+    let _: Glif<()> = pathops::remove_overlap(&info).unwrap();
+    let argv = argv(&path);
+    assert_eq!(argv[..3], ["BOOLEAN", "-i", path.to_str().unwrap()]);
+    assert_eq!(argv[5..], ["-m", "union"]);
+
+    let (info, path) = glyph("pathops-difference");
+    let (operand, operand_path) = glyph("pathops-operand");
+    let _: Glif<()> = pathops::difference(&info, &operand).unwrap();
+    assert_eq!(argv(&path)[5..], ["-m", "difference", "-p", operand_path.to_str().unwrap()]);
+}
+
+#[test]
+fn pathops_refigure() {
+    let (info, path) = glyph("pathops-refigure");
+    let refigured: Glif<()> = pathops::refigure(&info).unwrap();
+    assert_eq!(refigured.name, l().name);
+    let argv = argv(&path);
+    assert_eq!(argv[..4], ["REFIGURE", "-i", path.to_str().unwrap(), "-o"]);
+    assert_eq!(argv.len(), 5);
+}
+
+#[test]
+fn module_timeout() {
+    let (info, path) = glyph("hang");
+    let started = Instant::now();
+    let res: Result<Glif<()>, _> = stroke::stroke_with_timeout(&info, ConstantWidth::new(5.), Duration::from_millis(200));
+    assert!(matches!(res, Err(HelperError::TimedOut(t)) if t == Duration::from_millis(200)));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(argv(&path)[0], "CWS");
+
+    let res: Result<Glif<()>, _> = pathops::refigure_with_timeout(&info, Duration::from_millis(200));
+    assert!(matches!(res, Err(HelperError::TimedOut(_))));
+}
+
+#[test]
+fn module_orphan_holds_output() {
+    // exits at once, but leaves a child holding its stdout and stderr open
+    let (info, _) = glyph("orphan");
+    let started = Instant::now();
+    let res: Result<Glif<()>, _> = stroke::stroke_with_timeout(&info, ConstantWidth::new(5.), Duration::from_millis(500));
+    assert!(res.is_ok());
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(logged(log::Level::Warn, "holds its output open"));
+}
+
+#[test]
+fn module_newer_than_minimum() {
+    setup();
+    let found = KnownModule::Glif.available().unwrap();
+    assert_eq!((found.version.as_deref(), found.up_to_date), (Some("99.0.0"), true));
+    assert!(!logged(log::Level::Warn, "Expected MFEKglif"));
+}
+
+#[test]
+fn module_version_timeout() {
+    let dir = setup().join("slow");
+    fs::create_dir_all(&dir).unwrap();
+    let (bin, pid) = (dir.join("MFEKslowversion"), dir.join("pid"));
+    fs::write(&bin, format!("#!/bin/sh\necho $$ > {:?}\nexec sleep 30\n", pid)).unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let started = Instant::now();
+    let found = module::discover(&SearchPath::new().dir(&dir), "slowversion", "1.0").found;
+    assert_eq!(found, Some((Version::OutOfDate(None), bin)));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    // killed, not left sleeping
+    let pid = fs::read_to_string(pid).unwrap();
+    assert!(!process::Command::new("kill").args(["-0", pid.trim()]).status().unwrap().success());
+}
+
+#[test]
+fn module_failure() {
+    let (info, _) = glyph("fail");
+    let res: Result<Glif<()>, _> = stroke::stroke(&info, ConstantWidth::new(5.));
+    assert!(matches!(res, Err(HelperError::Exit(ExitError::Module(ExitCode::FontCorrupt)))));
+    // what it said on the way out is logged, as a warning
+    assert!(logged(log::Level::Warn, "no good"));
+}
+
+#[test]
+fn module_stderr() {
+    // more than fits in a pipe, which mustn't block it
+    let (info, _) = glyph("noisy");
+    let _: Glif<()> = pathops::remove_overlap(&info).unwrap();
+    assert!(logged(log::Level::Debug, "noise"));
+}
+
+/// A font of its own for the test `name`, and how its MFEKmetadata has been started so far.
+fn font(name: &str) -> (IPCInfo, impl Fn() -> Vec<String>) {
+    let path = setup().join(format!("{}.ufo", name));
+    fs::create_dir_all(&path).unwrap();
+    let starts = setup().join(format!("{}.ufo.starts", name));
+    let _ = fs::remove_file(&starts);
+    let info = IPCInfo::from_font_dir("test".to_string(), &path);
+    (info, move || fs::read_to_string(&starts).unwrap_or_default().lines().map(str::to_string).collect())
+}
+
+#[test]
+fn metadata_session() {
+    let (info, starts) = font("session");
+    let mut session = MetadataSession::new(&info).unwrap();
+    assert!(session.persistent());
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    assert_eq!(starts(), ["server"]);
+}
+
+#[test]
+fn metadata_session_restart() {
+    // it exits after each query
+    let (info, starts) = font("session-onequery");
+    let mut session = MetadataSession::new(&info).unwrap();
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    assert_eq!(starts(), ["server", "server"]);
+}
+
+#[test]
+fn metadata_session_timeout() {
+    let (info, starts) = font("session-slow");
+    let mut session = MetadataSession::new(&info).unwrap().timeout(Duration::from_millis(200));
+    let started = Instant::now();
+    assert_eq!(session.ascender_descender(), Err(()));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    // killed and restarted once, for the retry
+    assert_eq!(starts(), ["server", "server"]);
+}
+
+#[test]
+fn metadata_session_stubborn() {
+    let (info, _) = font("session-stubborn");
+    let mut session = MetadataSession::new(&info).unwrap();
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    let started = Instant::now();
+    drop(session);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(logged(log::Level::Warn, "didn't exit when asked to"));
+    // its stderr is logged, not inherited
+    assert!(logged(log::Level::Debug, "not shutting down"));
+}
+
+#[test]
+fn metadata_session_fallback() {
+    let (info, starts) = font("session-noserver");
+    let mut session = MetadataSession::new(&info).unwrap();
+    assert!(!session.persistent());
+    assert_eq!(session.ascender_descender(), Ok((800., -200.)));
+    assert_eq!(starts(), ["server", "once"]);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::setup;
+use mfek_ipc::forward::{self, ForwardError};
+use mfek_ipc::lock::Lock;
+use mfek_ipc::IPCInfo;
+use std::sync::mpsc::channel;
+use test_log::test;
+
+#[test]
+fn forward_without_other_owner() {
+    setup();
+    let info = IPCInfo::from_glif_path("ipc".to_string(), &"test_data/FRBAmericanCursive-SOURCE.ufo/fontinfo.plist");
+    assert!(matches!(forward::forward_open(&info), Err(ForwardError::NoOwner)));
+
+    let _lock = Lock::acquire(&info).unwrap();
+    let (tx, _rx) = channel();
+    #[cfg(target_family = "unix")]
+    let listener = forward::launch(tx).unwrap();
+    #[cfg(not(target_family = "unix"))]
+    drop(tx);
+    assert!(matches!(forward::forward_open(&info), Err(ForwardError::OwnedBySelf)));
+
+    // the socket goes with the listener
+    #[cfg(target_family = "unix")]
+    {
+        let socket = common::runtime_dir().join(format!("mfek/sockets/{}.open.sock", std::process::id()));
+        assert!(socket.exists());
+        drop(listener);
+        assert!(!socket.exists());
+    }
+}
+
+/// The owner for [`forward_to_other_process`], run by it in a child process, as the owner must
+/// have another pid. Locks and listens, then reports the request it gets (refusing it if the glyph
+/// is `refuse.glif`), and stays until its stdin closes.
+#[cfg(target_family = "unix")]
+#[test]
+#[ignore = "run by forward_to_other_process"]
+fn forward_helper() {
+    use std::io::{self, Read as _};
+    use std::path::PathBuf;
+    let glyph = match common::helper_arg() {
+        Some(glyph) => PathBuf::from(glyph),
+        None => return,
+    };
+    let info = IPCInfo::from_glif_path("helper".to_string(), &glyph);
+    let _lock = Lock::acquire(&info).unwrap();
+    let (tx, rx) = channel();
+    let _listener = forward::launch(tx).unwrap();
+    let rx = Some(rx).filter(|_| glyph.file_name().unwrap() != "refuse.glif");
+    println!("ready");
+    if let Some(rx) = &rx {
+        println!("from {}", rx.recv().unwrap().from_pid);
+    }
+    io::stdin().read_to_end(&mut vec![]).unwrap();
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn forward_to_other_process() {
+    use std::io::{BufRead as _, BufReader};
+    use std::process::{self, Stdio};
+    use std::{fs, thread};
+    setup();
+    fs::create_dir_all(common::runtime_dir()).unwrap();
+
+    for name in ["accept.glif", "refuse.glif"] {
+        let glyph = common::runtime_dir().join(name);
+        fs::copy("test_data/glyphs/l.glif", &glyph).unwrap();
+        let mut helper = common::helper("forward_helper", &glyph)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (lines_tx, lines) = channel();
+        let stdout = BufReader::new(helper.stdout.take().unwrap());
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                let _ = lines_tx.send(line);
+            }
+        });
+        let mut next_line = |prefix: &str| loop {
+            let line = lines.recv_timeout(std::time::Duration::from_secs(10)).expect("helper stopped talking");
+            if line.starts_with(prefix) {
+                break line;
+            }
+        };
+        next_line("ready");
+
+        let info = IPCInfo::from_glif_path("ipc".to_string(), &glyph);
+        if name == "accept.glif" {
+            assert_eq!(forward::forward_open(&info).unwrap(), helper.id());
+            assert_eq!(next_line("from "), format!("from {}", process::id()));
+        } else {
+            assert!(matches!(forward::forward_open(&info), Err(ForwardError::Refused)));
+        }
+
+        drop(helper.stdin.take());
+        assert!(helper.wait().unwrap().success());
+    }
+}
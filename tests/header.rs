@@ -1,5 +1,8 @@
+mod common;
+
 use test_log::test;
-use mfek_ipc::{display_header, header as ipc_header};
+use mfek_ipc::{compiled_date_utc, display_header, elaborate_header, header as ipc_header, source_date_epoch};
+use mfek_ipc::{header_html, header_plain, header_svg, header_to, HeaderColors, HeaderFont, HeaderStyle};
 use std::env;
 
 #[test]
@@ -7,7 +10,133 @@ fn header() {
     // ignore user colorize options just for this test
     env::remove_var("NO_COLOR");
     env::set_var("CLICOLOR_FORCE", "1");
+    env::remove_var("MFEK_HEADER_FONT");
+    env::remove_var("MFEK_HEADER_COLORS");
     let header = ipc_header("ipc");
     display_header("ipc");
     assert_eq!(header, include_bytes!("../test_data/header_ansi.txt"));
 }
+
+/// The epoch the tests' compile dates are at, 2021-12-19 00:00 UTC.
+const EPOCH: i64 = 1_639_872_000;
+const EPOCH_UTC: &str = "2021年12月19日(日)　00時00分00秒(午前)　協定世界時+0000";
+
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[test]
+fn elaborate_header_reproducible() {
+    // the same epoch gives the same bytes, whenever and wherever it's run
+    assert_eq!(compiled_date_utc(EPOCH).unwrap(), EPOCH_UTC);
+    // out of chrono's range, so left out rather than panicking
+    assert_eq!(compiled_date_utc(i64::MAX), None);
+
+    let line = strip_ansi(&String::from_utf8(elaborate_header("ipc", "1.0", Some(EPOCH))).unwrap());
+    #[cfg(feature = "reproducible-build")]
+    match source_date_epoch() {
+        Some(epoch) => assert_eq!(line, format!("This is MFEKipc 1.0, compiled @ {}.\n", compiled_date_utc(epoch).unwrap())),
+        None => assert_eq!(line, "This is MFEKipc 1.0.\n"),
+    }
+    #[cfg(not(feature = "reproducible-build"))]
+    {
+        assert_eq!(source_date_epoch(), None);
+        assert!(line.starts_with("This is MFEKipc 1.0, compiled @ 2021年12月"));
+        assert_eq!(elaborate_header("ipc", "1.0", Some(i64::MAX)), b"This is MFEKipc 1.0.\n");
+    }
+}
+
+/// Prints the header line for the epoch it's given, for [`elaborate_header_time_zone`] to run
+/// under different `TZ`s.
+#[cfg(target_family = "unix")]
+#[test]
+#[ignore = "run by elaborate_header_time_zone"]
+fn elaborate_header_helper() {
+    if let Some(epoch) = common::helper_arg().and_then(|e| e.to_str()?.parse().ok()) {
+        print!("{}", strip_ansi(&String::from_utf8(elaborate_header("ipc", "1.0", Some(epoch))).unwrap()));
+    }
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn elaborate_header_time_zone() {
+    let in_zone = |tz: &str, epoch: i64| {
+        let out = common::helper("elaborate_header_helper", epoch.to_string())
+            .env("TZ", tz)
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        String::from_utf8(out.stdout)
+            .unwrap()
+            .lines()
+            .find(|l| l.starts_with("This is MFEK"))
+            .unwrap()
+            .to_string()
+    };
+    let (utc, tokyo) = (in_zone("UTC", EPOCH), in_zone("Asia/Tokyo", EPOCH));
+    // reproducible builds print UTC wherever they're run, others the local time
+    #[cfg(feature = "reproducible-build")]
+    assert_eq!(utc, tokyo);
+    #[cfg(not(feature = "reproducible-build"))]
+    {
+        assert!(utc.contains("00時00分00秒"), "{}", utc);
+        assert!(tokyo.contains("09時00分00秒"), "{}", tokyo);
+        // the offset then, not now: winter and summer time
+        assert!(in_zone("Europe/Berlin", EPOCH).ends_with("協定世界時+0100."));
+        assert!(in_zone("Europe/Berlin", 1_657_000_000).ends_with("協定世界時+0200."));
+    }
+}
+
+#[test]
+fn header_style_plain_fallback() {
+    let style = HeaderStyle::new().colors(HeaderColors::Plain);
+    let latin = String::from_utf8(style.render("ipc")).unwrap();
+    assert!(!latin.contains('\x1b'));
+    assert!(!latin.contains("ipc"));
+    // no CJK in slant.flf, so the name is printed as is, on the logo's bottom line
+    let cjk = String::from_utf8(style.render("字形")).unwrap();
+    assert!(cjk.trim_end().lines().last().unwrap().ends_with("字形"));
+    let dropped = String::from_utf8(style.fallback(false).render("字形")).unwrap();
+    assert!(!dropped.contains("字形"));
+}
+
+#[test]
+fn header_style_parse() {
+    assert_eq!("standard".parse(), Ok(HeaderFont::Standard));
+    assert_eq!("none".parse(), Ok(HeaderColors::Plain));
+    assert_eq!("#FF8000".parse(), Ok(HeaderColors::Solid((255, 128, 0))));
+    assert_eq!("#ff0000:#0000ff".parse(), Ok(HeaderColors::Gradient((255, 0, 0), (0, 0, 255))));
+    assert_eq!("#ff00".parse::<HeaderColors>(), Err(()));
+}
+
+fn default_style_env() {
+    env::remove_var("NO_COLOR");
+    env::set_var("CLICOLOR_FORCE", "1");
+    env::remove_var("MFEK_HEADER_FONT");
+    env::remove_var("MFEK_HEADER_COLORS");
+}
+
+#[test]
+fn header_writer() {
+    default_style_env();
+    let mut buf = vec![];
+    header_to("ipc", &mut buf).unwrap();
+    assert_eq!(buf, include_bytes!("../test_data/header_ansi.txt"));
+}
+
+#[test]
+fn header_markup() {
+    default_style_env();
+    assert_eq!(header_plain("ipc"), include_str!("../test_data/header_plain.txt"));
+    assert_eq!(header_html("ipc"), include_str!("../test_data/header.html"));
+    assert_eq!(header_svg("ipc"), include_str!("../test_data/header.svg"));
+}
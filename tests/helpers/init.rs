@@ -0,0 +1,18 @@
+use mfek_ipc::helpers::init::*;
+use test_log::{self, test};
+#[test]
+fn test_glif_file_name() {
+    assert_eq!(glif_file_name("a"), "a.glif");
+    assert_eq!(glif_file_name("A"), "A_.glif");
+    assert_eq!(glif_file_name("AE"), "A_E_.glif");
+    assert_eq!(glif_file_name(".notdef"), "_notdef.glif");
+    assert_eq!(glif_file_name("con"), "_con.glif");
+    assert_eq!(glif_file_name("a*b/c"), "a_b_c.glif");
+}
+#[test]
+fn test_font_options() {
+    let options = FontOptions::default().upm(2048).family("Test").layer("sketches");
+    assert_eq!(options.upm, 2048);
+    assert_eq!(options.family.as_deref(), Some("Test"));
+    assert_eq!(options.layers, vec!["sketches".to_string()]);
+}
@@ -19,3 +19,30 @@ fn test_asc_desc() {
     assert_eq!(a_d.0, 650.0);
     assert_eq!(a_d.1, -350.0);
 }
+#[test]
+fn test_session_needs_font() {
+    let info = IPCInfo::new_disconnected();
+    assert!(MetadataSession::new(&info).is_err());
+}
+#[test]
+fn test_guidelines_from_json() {
+    let json = r#"[{"x": 100}, {"y": 250.5, "name": "x-height"}, {"x": 1, "y": 2, "angle": 45, "color": "1,0,0,0.5"}, {"x": 1, "y": 2}, {"angle": 30}]"#;
+    let guidelines: Vec<glifparser::Guideline<()>> = guidelines_from_json(json).unwrap();
+    assert_eq!(guidelines.len(), 3);
+    assert_eq!((guidelines[0].at.x, guidelines[0].at.y), (100., 0.));
+    assert_eq!(guidelines[0].name, None);
+    assert_eq!((guidelines[1].at.x, guidelines[1].at.y), (0., 250.5));
+    assert_eq!(guidelines[1].name.as_deref(), Some("x-height"));
+    assert_eq!((guidelines[2].at.x, guidelines[2].at.y), (1., 2.));
+}
+#[test]
+fn test_all_guidelines() {
+    let mut info = IPCInfo::new_disconnected();
+    info.font = Some(("test_data/FRBAmericanCursive-SOURCE.ufo/").into());
+    info.glyph = Some(("test_data/glyphs/l.glif").into());
+    let all = all_guidelines::<()>(&info).unwrap();
+    assert!(!all.font.is_empty());
+    assert!(all.font.iter().all(|g| g.name.is_none()));
+    assert!(all.glyph.is_empty());
+    assert_eq!(all.iter().count(), all.font.len());
+}
@@ -1 +1,3 @@
+mod init;
 mod metadata;
+mod stroke;
@@ -0,0 +1,10 @@
+use mfek_ipc::helpers::stroke::*;
+use test_log::{self, test};
+#[test]
+fn test_stroke_modes() {
+    let cws = ConstantWidth::new(20.);
+    assert_eq!(StrokeMode::from(cws.clone()), StrokeMode::ConstantWidth(cws));
+    let pap = PatternAlongPath::new("test_data/glyphs/l.glif");
+    assert_eq!(pap.scale, (1., 1.));
+    assert!(matches!(StrokeMode::from(Variable::default()), StrokeMode::Variable(_)));
+}
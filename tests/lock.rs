@@ -0,0 +1,77 @@
+mod common;
+
+use common::setup;
+use mfek_ipc::lock::{Lock, LockError};
+use mfek_ipc::IPCInfo;
+use std::process;
+use test_log::test;
+
+#[test]
+fn lock_held_and_released() {
+    setup();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/FRBAmericanCursive-SOURCE.ufo/fontinfo.plist".into());
+
+    assert!(Lock::holder(&info).is_none());
+    let lock = Lock::acquire(&info).unwrap();
+    match Lock::acquire(&info) {
+        Err(LockError::Held(holder)) => {
+            assert_eq!(holder.pid, process::id());
+            assert_eq!(holder.module, info.parent_module);
+        }
+        other => panic!("expected lock to be held, got {:?}", other),
+    }
+    assert_eq!(Lock::holder(&info).as_ref(), Some(lock.info()));
+    lock.release();
+    assert!(Lock::holder(&info).is_none());
+    assert!(Lock::acquire(&info).is_ok());
+
+    info.glyph = None;
+    assert!(matches!(Lock::acquire(&info), Err(LockError::NoTarget)));
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn lock_stale_recovered_once() {
+    use std::sync::Barrier;
+    use std::{fs, mem, thread};
+    setup();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/glyphs/l.glif".into());
+
+    // leave behind a lock held by a process that's gone
+    let lock = Lock::acquire(&info).unwrap();
+    let file = fs::read_dir(common::runtime_dir().join("mfek/locks"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension() == Some("lock".as_ref()))
+        .find(|p| fs::read_to_string(p).map_or(false, |data| data.contains("l.glif")))
+        .unwrap();
+    let mut dead = process::Command::new("true").spawn().unwrap();
+    dead.wait().unwrap();
+    let mut stale = lock.info().clone();
+    stale.pid = dead.id();
+    fs::write(&file, serde_json::to_string(&stale).unwrap()).unwrap();
+    mem::forget(lock);
+    assert!(Lock::holder(&info).is_none());
+
+    // of many taking it over at once, exactly one gets it, and nobody sees it half written
+    let barrier = Barrier::new(8);
+    let locks: Vec<Result<Lock, LockError>> = thread::scope(|scope| {
+        let contenders: Vec<_> = (0..8)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    Lock::acquire(&info)
+                })
+            })
+            .collect();
+        contenders.into_iter().map(|c| c.join().unwrap()).collect()
+    });
+    assert_eq!(locks.iter().filter(|l| l.is_ok()).count(), 1);
+    assert!(locks.iter().all(|l| match l {
+        Err(LockError::Held(holder)) => holder.pid == process::id(),
+        other => other.is_ok(),
+    }));
+    assert_eq!(Lock::holder(&info).map(|h| h.pid), Some(process::id()));
+}
@@ -24,3 +24,198 @@ fn ipcinfo() {
     let s: String = ipcinfo.parent_exe.to_str().unwrap().to_string();
     assert!(s.contains("ipc.rlib"));
 }
+
+#[test]
+fn cli_version_roundtrip() {
+    use mfek_ipc::cli::{self, Help};
+    let version = env!("CARGO_PKG_VERSION");
+    let human = cli::std_args_from(&["--version"], KMD, version, &Help::default()).unwrap();
+    assert_eq!(module::version_from_output(&human).as_deref(), Some(version));
+    let json = cli::std_args_from(&["--version", "--json"], KMD, version, &Help::default()).unwrap();
+    assert_eq!(module::version_from_output(&json).as_deref(), Some(version));
+    assert!(cli::std_args_from(&["--help"], KMD, version, &Help::default()).unwrap().starts_with(&cli::version_string(KMD, version)));
+    assert!(cli::std_args_from(&["font.ufo"], KMD, version, &Help::default()).is_none());
+    // paths needn't be UTF-8 on Unix
+    #[cfg(target_family = "unix")]
+    {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt as _;
+        let font = OsStr::from_bytes(b"fon\xfft.ufo");
+        assert!(cli::std_args_from(&[font], KMD, version, &Help::default()).is_none());
+        assert!(cli::std_args_from(&[font, OsStr::new("--version")], KMD, version, &Help::default()).is_some());
+    }
+}
+
+#[test]
+fn module_require_missing() {
+    let missing = module::require("nonexistentmodule", env!("CARGO_PKG_VERSION"));
+    assert_eq!(missing, Err(module::ModuleError::Missing("nonexistentmodule".to_string())));
+    assert!(missing.unwrap_err().to_string().contains("MFEKnonexistentmodule"));
+}
+
+#[test]
+fn metadata_binary_cached() {
+    let (_, path) = module::available(KMD, env!("CARGO_PKG_VERSION")).unwrap();
+    assert_eq!(mfek_ipc::helpers::metadata::binary(), Ok(path));
+}
+
+#[test]
+fn module_search_path() {
+    use mfek_ipc::module::{SearchOrder, SearchPath};
+    use std::env;
+    let bindir = env::current_exe().unwrap().parent().unwrap().to_owned();
+    let search = SearchPath::new().dir("/nonexistent/mfek").order(SearchOrder::BundleFirst);
+    let dirs = search.dirs();
+    assert_eq!(dirs[0], std::path::PathBuf::from("/nonexistent/mfek"));
+    assert_eq!(dirs[1], bindir);
+
+    let search = SearchPath::from_vars([("MFEK_MODULE_PATH", "/nonexistent/a"), ("MFEK_PIN_NOSUCHMODULE", "/nonexistent/MFEKnosuchmodule")]);
+    assert_eq!(search.dirs()[0], std::path::PathBuf::from("/nonexistent/a"));
+    assert!(module::available_in(&search, "nosuchmodule", "0.0.0").is_err());
+    assert_eq!(module::available_in(&search, KMD, env!("CARGO_PKG_VERSION")), module::available(KMD, env!("CARGO_PKG_VERSION")));
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn module_trust_policy() {
+    use mfek_ipc::module::{SearchPath, TrustPolicy, Version};
+    use std::os::unix::fs::PermissionsExt;
+    use std::{env, fs};
+    let dir = env::temp_dir().join(format!("mfek-ipc-test-trust-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let bin = dir.join("MFEKtrusttest");
+    fs::write(&bin, "#!/bin/sh\necho MFEKtrusttest 1.0\n").unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+
+    let search = SearchPath::new().dir(&dir);
+    let found = module::discover(&search, "trusttest", "1.0");
+    assert_eq!(found.found, Some((Version::UpToDate("1.0"), bin.clone())));
+    assert!(found.rejected.is_empty());
+
+    let strict = module::discover(&search.clone().trust(TrustPolicy::strict()), "trusttest", "1.0");
+    assert_eq!(strict.found, None);
+    assert_eq!(strict.rejected[0].path, bin);
+    assert!(strict.rejected[0].reason.contains("world-writable"));
+
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+    let allow = TrustPolicy::default().allow_sha256("00");
+    let hashed = module::discover(&search.trust(allow), "trusttest", "1.0");
+    assert!(hashed.rejected[0].reason.contains("SHA-256"));
+}
+
+#[test]
+fn known_modules() {
+    use mfek_ipc::module::{version_at_least, KnownModule};
+    assert_eq!("metadata".parse(), Ok(KnownModule::Metadata));
+    assert_eq!("MFEKpathops".parse(), Ok(KnownModule::PathOps));
+    assert_eq!(KnownModule::from("mfek-foo"), KnownModule::Other("foo".to_string()));
+    assert_eq!(KnownModule::Glif.to_string(), "MFEKglif");
+    assert_eq!(KnownModule::Metadata.binaries(), module::binaries(KMD));
+    assert!(KnownModule::ALL.iter().all(|m| !m.purpose().is_empty()));
+
+    assert!(version_at_least("1.2.0", "1.2"));
+    assert!(version_at_least("v1.10.0", "1.9.3"));
+    assert!(!version_at_least("1.2.0-beta1", "1.2.0"));
+    assert!(version_at_least("1.2.0-beta2", "1.2.0-beta1"));
+    assert!(!version_at_least("unknown", "0.0.0"));
+
+    let found = KnownModule::Metadata.available().unwrap();
+    assert!(found.up_to_date);
+    assert_eq!(found.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+    assert_eq!(KnownModule::Metadata.require(), Ok(found.path));
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn module_manifests() {
+    use mfek_ipc::module::{manifests_in, KnownModule, Manifest, SearchPath, Version};
+    use std::os::unix::fs::PermissionsExt;
+    use std::{env, fs};
+    let data = env::temp_dir().join(format!("mfek-ipc-test-data-{}", process::id()));
+    let (home, system) = (data.join("home/mfek/modules"), data.join("system/mfek/modules"));
+    for dir in [&home, &system] {
+        fs::create_dir_all(dir.join("bin")).unwrap();
+    }
+    let script = |path: &std::path::Path, body: &str| {
+        fs::write(path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    let bin = system.join("bin/plugin");
+    script(&bin, "echo MFEKplugintest 2.1");
+    fs::write(
+        system.join("plugintest.toml"),
+        "name = \"plugintest\"\nbinary = \"bin/plugin\"\nversion = \"2.1\"\nfile_types = [\"glif\"]\n\n[[menu]]\nlabel = \"Test\"\nargs = [\"--test\"]\n",
+    )
+    .unwrap();
+    fs::write(home.join("plugintest.json"), r#"{"name": "plugintest", "binary": "/nonexistent/MFEKplugintest"}"#).unwrap();
+    fs::write(system.join("broken.json"), "{").unwrap();
+    script(&system.join("bin/impostor"), "echo MFEKmetadata 0.0.0");
+    fs::write(system.join("metadata.json"), r#"{"name": "metadata", "binary": "bin/impostor"}"#).unwrap();
+
+    // the one in the first directory wins, though its binary doesn't exist; the built-in is skipped
+    let manifests = manifests_in(&[home.clone(), system.clone()]);
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].binary, std::path::PathBuf::from("/nonexistent/MFEKplugintest"));
+    let search = SearchPath::new().manifest(manifests[0].clone());
+    assert!(module::available_in(&search, "plugintest", "2.1").is_err());
+    assert!(module::list_modules_in(&search).iter().all(|l| l.module.name() != "plugintest"));
+
+    let manifest = Manifest::read(&system.join("plugintest.toml")).unwrap();
+    assert_eq!(manifest.binary, bin);
+    assert!(manifest.handles(".GLIF"));
+    assert_eq!(manifest.menu[0].args, vec!["--test".to_string()]);
+    let search = SearchPath::new().manifest(manifest.clone());
+    assert_eq!(module::available_in(&search, "plugintest", "2.1"), Ok((Version::UpToDate("2.1"), bin.clone())));
+    let listed = module::list_modules_in(&search);
+    let plugin = listed.iter().find(|l| l.module == KnownModule::Other("plugintest".to_string())).unwrap();
+    assert_eq!(plugin.binary, bin);
+    assert_eq!(plugin.manifest, Some(manifest.clone()));
+
+    // a binary that doesn't say its version has the manifest's
+    script(&bin, "exit 0");
+    assert_eq!(module::available_in(&search, "plugintest", "2.1"), Ok((Version::UpToDate("2.1"), bin.clone())));
+    assert_eq!(module::available_in(&search, "plugintest", "3.0"), Ok((Version::OutOfDate(Some("2.1".to_string())), bin.clone())));
+
+    // even given directly, a manifest can't replace a built-in module
+    let impostor = Manifest::read(&system.join("metadata.json")).unwrap();
+    let search = SearchPath::new().manifest(impostor.clone());
+    assert_ne!(module::available_in(&search, KMD, env!("CARGO_PKG_VERSION")).map(|(_, path)| path), Ok(impostor.binary));
+    assert!(module::list_modules_in(&search).iter().all(|l| l.manifest.is_none()));
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn exit_codes() {
+    use mfek_ipc::exit::{ExitCode, ExitError};
+    let status = |script: &str| process::Command::new("sh").args(["-c", script]).status().unwrap();
+    for code in ExitCode::ALL {
+        assert_eq!(ExitCode::from_code(code.code()), Some(code));
+    }
+    assert_eq!(ExitCode::check(status("exit 0")), Ok(()));
+    assert_eq!(ExitCode::check(status("exit 66")), Err(ExitError::Module(ExitCode::FontNotFound)));
+    assert_eq!(ExitCode::check(status("exit 130")), Err(ExitError::Module(ExitCode::Cancelled)));
+    assert_eq!(ExitCode::check(status("exit 3")), Err(ExitError::Unknown(3)));
+    assert_eq!(ExitCode::check(status("kill -9 $$")), Err(ExitError::Killed));
+    assert_eq!(ExitError::Module(ExitCode::FontCorrupt).to_string(), "font corrupt (exit code 65)");
+}
+
+#[test]
+fn ipc_negotiation() {
+    use mfek_ipc::module::Version;
+    use mfek_ipc::negotiate::{self, Parent, PROTOCOL, PROTOCOL_VAR, VERSION_VAR};
+    assert_eq!(Parent::from_vars(Vec::<(String, String)>::new()), None);
+
+    let mut command = process::Command::new("MFEKmetadata");
+    negotiate::stamp(&mut command);
+    let parent = Parent::from_vars(command.get_envs().map(|(var, value)| (var, value.unwrap()))).unwrap();
+    assert_eq!(parent.protocol, Some(PROTOCOL));
+    assert_eq!(negotiate::check_parent(parent), Some(Version::UpToDate(negotiate::VERSION)));
+
+    let old = Parent::from_vars([(VERSION_VAR, "0.0.1".to_string()), (PROTOCOL_VAR, (PROTOCOL + 1).to_string())]).unwrap();
+    assert_eq!(negotiate::check_parent(old), Some(Version::OutOfDate(Some("0.0.1".to_string()))));
+    assert_eq!(Parent::from_vars([(VERSION_VAR, "0.0.1"), (PROTOCOL_VAR, "one")]).unwrap().protocol, None);
+
+    let json: serde_json::Value = serde_json::from_str(&mfek_ipc::cli::version_json(KMD, "1.0")).unwrap();
+    assert_eq!(json["protocol"], PROTOCOL);
+}
@@ -0,0 +1,65 @@
+#![cfg(target_family = "unix")]
+mod common;
+
+use common::setup;
+use glifparser::Glif;
+use mfek_ipc::preview::{PreviewPublisher, PreviewSubscriber};
+use mfek_ipc::IPCInfo;
+use std::fs;
+use test_log::test;
+
+#[test]
+fn preview_publish_and_resync() {
+    setup();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/glyphs/l.glif".into());
+    let glif: Glif<()> = glifparser::read(&fs::read_to_string("test_data/glyphs/l.glif").unwrap()).unwrap();
+
+    let mut publisher = PreviewPublisher::bind(&info).unwrap();
+    assert!(PreviewPublisher::bind(&info).is_err());
+    assert_eq!(publisher.publish(&glif).unwrap(), 1);
+
+    // joining late still gets the latest state
+    let mut subscriber = PreviewSubscriber::connect(&info).unwrap();
+    let update = subscriber.recv::<()>().unwrap().unwrap();
+    assert_eq!((update.seq, update.missed), (1, 0));
+    assert_eq!(update.glif.name, glif.name);
+
+    assert_eq!(publisher.publish(&glif).unwrap(), 2);
+    assert_eq!(subscriber.recv::<()>().unwrap().unwrap().seq, 2);
+    subscriber.resync().unwrap();
+    assert_eq!(publisher.publish(&glif).unwrap(), 3);
+    // the resent #2 is a duplicate, so skipped
+    assert_eq!(subscriber.recv::<()>().unwrap().unwrap().seq, 3);
+
+    drop(publisher);
+    assert!(subscriber.recv::<()>().unwrap().is_none());
+    assert!(PreviewPublisher::bind(&info).is_ok());
+}
+
+#[test]
+fn preview_slow_subscriber_gets_last() {
+    setup();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/glyphs/slow.glif".into());
+    let glif: Glif<()> = glifparser::read(&fs::read_to_string("test_data/glyphs/l.glif").unwrap()).unwrap();
+
+    let mut publisher = PreviewPublisher::bind(&info).unwrap();
+    let mut subscriber = PreviewSubscriber::connect(&info).unwrap();
+    publisher.publish(&glif).unwrap();
+    assert_eq!(subscriber.recv::<()>().unwrap().unwrap().seq, 1);
+    // far more than the socket buffers, so the publisher has to drop some
+    for _ in 1..5000 {
+        publisher.publish(&glif).unwrap();
+    }
+    let (mut last, mut missed) = (0, 0);
+    while last < 5000 {
+        let update = subscriber.recv::<()>().unwrap().unwrap();
+        last = update.seq;
+        missed += update.missed;
+    }
+    assert!(missed > 0);
+
+    drop(publisher);
+    assert!(subscriber.recv::<()>().unwrap().is_none());
+}
@@ -0,0 +1,73 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use mfek_ipc::progress::{Progress, ProgressChannel, ProgressReporter, PROGRESS_VAR};
+use std::path::PathBuf;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
+use test_log::test;
+
+fn setup() {
+    static SETUP: Once = Once::new();
+    SETUP.call_once(|| {
+        common::setup();
+        env::remove_var(PROGRESS_VAR);
+    });
+}
+
+fn socket(channel: &ProgressChannel) -> PathBuf {
+    let mut command = process::Command::new("MFEKexport");
+    channel.attach(&mut command);
+    command.get_envs().find(|(k, _)| *k == PROGRESS_VAR).and_then(|(_, v)| v).unwrap().into()
+}
+
+#[test]
+fn progress_and_cancel() {
+    setup();
+    let channel = ProgressChannel::new().unwrap();
+    let path = socket(&channel);
+
+    // cancelled before the module even connected
+    channel.canceller().cancel();
+    let mut reporter = ProgressReporter::connect(&path);
+    reporter.report(0.5, Some("half"));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !reporter.is_cancelled() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(reporter.is_cancelled());
+    reporter.report(1.0, None);
+    drop(reporter);
+
+    let reports: Vec<Progress> = channel.iter().collect();
+    assert_eq!(
+        reports,
+        vec![Progress { progress: 0.5, message: Some("half".to_string()) }, Progress { progress: 1.0, message: None }]
+    );
+
+    let mut nowhere = ProgressReporter::from_env();
+    nowhere.report(0.1, None);
+    assert!(!nowhere.is_cancelled());
+}
+
+#[test]
+fn progress_until_exit() {
+    setup();
+    // a module that exits without ever connecting
+    let channel = ProgressChannel::new().unwrap();
+    let mut child = channel.attach(&mut process::Command::new("true")).spawn().unwrap();
+    let started = Instant::now();
+    assert_eq!(channel.iter_until(&mut child).count(), 0);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(child.wait().unwrap().success());
+
+    // reports sent before it exited are still read
+    let channel = ProgressChannel::new().unwrap();
+    let mut reporter = ProgressReporter::connect(&socket(&channel));
+    reporter.report(0.25, None);
+    drop(reporter);
+    let mut child = channel.attach(&mut process::Command::new("true")).spawn().unwrap();
+    assert_eq!(channel.iter_until(&mut child).collect::<Vec<_>>(), vec![Progress { progress: 0.25, message: None }]);
+}
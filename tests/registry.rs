@@ -0,0 +1,25 @@
+mod common;
+
+use mfek_ipc::registry::{self, running_instances};
+use mfek_ipc::IPCInfo;
+use std::path::Path;
+use std::process;
+use test_log::test;
+
+#[test]
+fn register_and_query() {
+    common::setup();
+    let font = Path::new("test_data/FRBAmericanCursive-SOURCE.ufo");
+    let mut info = IPCInfo::new_disconnected();
+    info.font = Some(font.into());
+
+    let registration = registry::register(&info, env!("CARGO_PKG_VERSION")).unwrap();
+    let found = running_instances(Some(&info.parent_module), Some(font));
+    assert_eq!(found, vec![registration.instance().clone()]);
+    assert_eq!(found[0].pid, process::id());
+    assert!(running_instances(Some("nonexistent"), None).is_empty());
+    assert!(running_instances(None, Some(Path::new("test_data"))).is_empty());
+
+    registration.deregister();
+    assert!(running_instances(None, Some(font)).is_empty());
+}
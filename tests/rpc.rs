@@ -0,0 +1,64 @@
+use mfek_ipc::rpc::{self, Id, Response, RpcError, Server};
+use serde_json::json;
+use std::io::Cursor;
+use std::thread;
+use std::time::Duration;
+use test_log::test;
+
+fn serve(input: &str) -> Vec<Response> {
+    let server = Server::new()
+        .handler("add", |(a, b): (i64, i64), _| Ok::<_, RpcError>(a + b))
+        .handler("panic", |_: (), _| -> Result<i64, RpcError> { panic!("handler bug") })
+        .handler("wait", |_: (), cancel| {
+            while !cancel.is_cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Ok::<_, RpcError>("done")
+        });
+    let mut output = vec![];
+    server.serve(Cursor::new(input), &mut output).unwrap();
+    String::from_utf8(output).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+}
+
+#[test]
+fn rpc_requests_and_errors() {
+    let responses = serve(concat!(
+        r#"{"jsonrpc":"2.0","id":1,"method":"add","params":[2,3]}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":2,"method":"subtract","params":[2,3]}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":3,"method":"add","params":{"a":2}}"#, "\n",
+        r#"{"jsonrpc":"2.0","method":"add","params":[0,0]}"#, "\n",
+        "{not json\n",
+        r#"{"jsonrpc":"2.0","id":4,"method":"ping"}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":5,"method":"panic"}"#, "\n",
+        r#"{"jsonrpc":"1.0","id":6,"method":"add","params":[2,3]}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":7,"method":"add","params":[4,5]}"#, "\n",
+    ));
+    let by_id = |id: i64| responses.iter().find(|r| r.id == Some(Id::Number(id))).unwrap();
+    assert_eq!(responses.len(), 8);
+    assert_eq!(by_id(4).result, Some(json!(mfek_ipc::negotiate::PROTOCOL)));
+    assert_eq!(by_id(1).result, Some(json!(5)));
+    assert_eq!(by_id(2).error.as_ref().unwrap().code, rpc::METHOD_NOT_FOUND);
+    assert_eq!(by_id(3).error.as_ref().unwrap().code, rpc::INVALID_PARAMS);
+    // the server outlives a panicking handler
+    assert_eq!(by_id(5).error.as_ref().unwrap().code, rpc::INTERNAL_ERROR);
+    assert_eq!(by_id(6).error.as_ref().unwrap().code, rpc::INVALID_REQUEST);
+    assert_eq!(by_id(7).result, Some(json!(9)));
+    assert!(responses.iter().any(|r| r.id.is_none() && r.error.as_ref().unwrap().code == rpc::PARSE_ERROR));
+}
+
+#[test]
+fn rpc_cancel_and_shutdown() {
+    let responses = serve(concat!(
+        r#"{"jsonrpc":"2.0","id":"w","method":"wait"}"#, "\n",
+        r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":"w"}}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":9,"method":"shutdown"}"#, "\n",
+        r#"{"jsonrpc":"2.0","id":10,"method":"add","params":[1,1]}"#, "\n",
+    ));
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].id, Some(Id::String("w".to_string())));
+    assert_eq!(responses[0].error.as_ref().unwrap().code, rpc::REQUEST_CANCELLED);
+    // shutdown is answered last, and nothing after it is served
+    assert_eq!(responses[1].id, Some(Id::Number(9)));
+    // a `null` result reads back as `None`
+    assert!(responses[1].result.is_none() && responses[1].error.is_none());
+}
@@ -0,0 +1,27 @@
+#![cfg(target_family = "unix")]
+mod common;
+
+use mfek_ipc::lock::{Lock, LockError};
+use mfek_ipc::IPCInfo;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use test_log::test;
+
+#[test]
+fn runtime_dir_not_private() {
+    common::setup();
+    let runtime_dir = common::runtime_dir();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/glyphs/l.glif".into());
+
+    // as if somebody else had made it first, for all to write to
+    fs::create_dir_all(runtime_dir.join("mfek")).unwrap();
+    fs::set_permissions(runtime_dir.join("mfek"), fs::Permissions::from_mode(0o777)).unwrap();
+    match Lock::acquire(&info) {
+        Err(LockError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied),
+        other => panic!("locked in a world-writable runtime directory: {:?}", other),
+    }
+
+    fs::set_permissions(runtime_dir.join("mfek"), fs::Permissions::from_mode(0o700)).unwrap();
+    assert!(Lock::acquire(&info).is_ok());
+}
@@ -0,0 +1,79 @@
+#![cfg(target_family = "unix")]
+
+use mfek_ipc::module::{ModuleError, SearchPath};
+use mfek_ipc::supervisor::{ChildState, RestartPolicy, Supervisor, SupervisorError};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{env, fs, process, thread};
+use test_log::test;
+
+fn script(dir: &Path, name: &str, body: &str) {
+    let bin = dir.join(name);
+    fs::write(&bin, format!("#!/bin/sh\n[ \"$1\" = --version ] && echo {} 1.0 && exit 0\n{}\n", name, body)).unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn wait_for(supervisor: &Supervisor, id: mfek_ipc::supervisor::ChildId, done: impl Fn(ChildState) -> bool) -> ChildState {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let state = supervisor.state(id).unwrap();
+        if done(state) || Instant::now() > deadline {
+            return state;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn supervisor_restarts_and_stops() {
+    let dir = env::temp_dir().join(format!("mfek-ipc-test-supervisor-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    script(&dir, "MFEKfailing", "exit 3");
+    script(&dir, "MFEKsleeping", "sleep 30");
+    let search = SearchPath::new().dir(&dir);
+    let supervisor = Supervisor::new();
+
+    let policy = RestartPolicy::OnFailure { max_restarts: 2, backoff: Duration::from_millis(10), max_backoff: Duration::from_millis(50) };
+    let failing = supervisor.spawn_in(&search, "failing", "1.0", ["--now"], policy).unwrap();
+    match wait_for(&supervisor, failing, |s| matches!(s, ChildState::Exited(_))) {
+        ChildState::Exited(status) => assert_eq!(status.code(), Some(3)),
+        other => panic!("expected it to give up, got {:?}", other),
+    }
+    assert_eq!(supervisor.restarts(failing), Some(2));
+
+    let sleeping = supervisor.spawn_in(&search, "sleeping", "1.0", Vec::<String>::new(), RestartPolicy::on_failure()).unwrap();
+    let pid = match supervisor.state(sleeping) {
+        Some(ChildState::Running { pid }) => pid,
+        other => panic!("expected it to be running, got {:?}", other),
+    };
+    supervisor.stop(sleeping).unwrap();
+    assert_eq!(supervisor.state(sleeping), Some(ChildState::Stopped));
+    // its process group is gone, and it isn't restarted
+    let alive = process::Command::new("kill").args(["-0", "--", &format!("-{}", pid)]).stderr(process::Stdio::null()).status().unwrap();
+    assert!(!alive.success());
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(supervisor.state(sleeping), Some(ChildState::Stopped));
+
+    assert!(matches!(
+        supervisor.spawn_in(&search, "nosuchmodule", "1.0", Vec::<String>::new(), RestartPolicy::Never),
+        Err(SupervisorError::Module(ModuleError::Missing(_)))
+    ));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn supervisor_outlives_spawning_thread() {
+    let dir = env::temp_dir().join(format!("mfek-ipc-test-supervisor-thread-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    script(&dir, "MFEKsleeping", "sleep 30");
+    let search = SearchPath::new().dir(&dir);
+    let supervisor = Supervisor::new();
+
+    // PR_SET_PDEATHSIG would kill it when this thread exits, were it started here
+    let id = thread::scope(|scope| {
+        scope.spawn(|| supervisor.spawn_in(&search, "sleeping", "1.0", Vec::<String>::new(), RestartPolicy::Never).unwrap()).join().unwrap()
+    });
+    thread::sleep(Duration::from_millis(500));
+    assert!(matches!(supervisor.state(id), Some(ChildState::Running { .. })));
+}
@@ -0,0 +1,100 @@
+#![cfg(target_family = "unix")]
+mod common;
+
+use common::{runtime_dir, setup};
+use mfek_ipc::sync::{PointRef, SyncChannel, Viewport};
+use mfek_ipc::IPCInfo;
+use std::io::Read as _;
+use std::process::Stdio;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+use std::{fs, io};
+use test_log::test;
+
+fn selection() -> Vec<PointRef> {
+    vec![PointRef { contour: 0, point: 2, identifier: None }]
+}
+
+#[test]
+fn sync_between_members() {
+    setup();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some("test_data/glyphs/l.glif".into());
+
+    let (tx, rx) = channel();
+    let mut ours = SyncChannel::join(&info, tx).unwrap();
+    let selection = selection();
+    ours.set_selection(selection.clone());
+    ours.set_viewport(Viewport { x: -50., y: -200., width: 400., height: 1000. });
+    // a member never follows itself
+    assert!(rx.recv_timeout(Duration::from_secs(2)).is_err());
+    // joining twice from one process is refused, as the socket is taken
+    let (tx2, _rx2) = channel();
+    assert!(SyncChannel::join(&info, tx2).is_err());
+    assert_eq!(ours.state().selection, selection);
+}
+
+/// The other member of [`sync_across_processes`], run by it in a child process, as members are
+/// told apart by pid. Joins, selects and scrolls, then stays until its stdin closes.
+#[test]
+#[ignore = "run by sync_across_processes"]
+fn sync_helper() {
+    let glyph = match common::helper_arg() {
+        Some(glyph) => glyph,
+        None => return,
+    };
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some(glyph.into());
+    let (tx, _rx) = channel();
+    let mut theirs = SyncChannel::join(&info, tx).unwrap();
+    theirs.set_selection(selection());
+    theirs.set_viewport(Viewport { x: 0., y: -200., width: 800., height: 1000. });
+    io::stdin().read_to_end(&mut vec![]).unwrap();
+}
+
+#[test]
+fn sync_across_processes() {
+    setup();
+    // a glyph of its own, so the members of sync_between_members don't follow it
+    let glyph = runtime_dir().join("across.glif");
+    fs::create_dir_all(runtime_dir()).unwrap();
+    fs::copy("test_data/glyphs/l.glif", &glyph).unwrap();
+    let mut info = IPCInfo::new_disconnected();
+    info.glyph = Some(glyph.clone());
+
+    let (tx, rx) = channel();
+    let ours = SyncChannel::join(&info, tx).unwrap();
+    let mut helper = common::helper("sync_helper", &glyph)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // its selection and viewport may come in one message or two
+    let msg = loop {
+        let msg = rx.recv_timeout(Duration::from_secs(10)).expect("no sync message from the other member");
+        assert_eq!(msg.from_pid, helper.id());
+        if msg.state.viewport.is_some() {
+            break msg;
+        }
+    };
+    assert_eq!(msg.state.selection, selection());
+    assert_eq!(msg.state.viewport, Some(Viewport { x: 0., y: -200., width: 800., height: 1000. }));
+
+    // asked again, it sends its latest state again
+    ours.resync();
+    let again = rx.recv_timeout(Duration::from_secs(10)).expect("no resync from the other member");
+    assert_eq!(again, msg);
+
+    // and once we leave, nothing more is sent our way
+    drop(ours);
+    loop {
+        match rx.recv_timeout(Duration::from_secs(10)) {
+            Ok(_) => continue,
+            Err(e) => break assert_eq!(e, RecvTimeoutError::Disconnected),
+        }
+    }
+
+    drop(helper.stdin.take());
+    assert!(helper.wait().unwrap().success());
+}